@@ -0,0 +1,109 @@
+//! Named strategies for folding a key's values from several descendant
+//! items into a single value on their parent, e.g. rolling track-level
+//! durations up into an album-level total.
+
+use std::convert::TryFrom;
+use std::cmp::Ordering;
+
+use bigdecimal::BigDecimal;
+
+use metadata::types::MetaVal;
+use functions::util::NumberLike;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggMethod {
+    Sum,
+    Min,
+    Max,
+    Join,
+    Count,
+    Average,
+}
+
+impl AggMethod {
+    /// Folds `values` according to this method, returning `None` if there
+    /// is nothing to write back (no values at all, or no values of a type
+    /// this method knows how to fold).
+    ///
+    /// `Sum`/`Min`/`Max`/`Average` fold over `NumberLike`, so `Int` and
+    /// `Dec` values mix freely (an `Int` promotes to `Dec` as soon as a
+    /// `Dec` is involved, same as `NumberLike`'s own `AddAssign`); anything
+    /// that isn't numeric is skipped instead of erroring. `Join` only
+    /// considers `MetaVal::Str` values, and preserves the order `values` is
+    /// given in.
+    pub fn fold<I>(self, values: I) -> Option<MetaVal>
+    where
+        I: IntoIterator<Item = MetaVal>,
+    {
+        match self {
+            AggMethod::Count => match values.into_iter().count() {
+                0 => None,
+                n => Some(MetaVal::Int(n as i64)),
+            },
+
+            AggMethod::Join => {
+                let joined = values.into_iter()
+                    .filter_map(Self::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                if joined.is_empty() { None } else { Some(MetaVal::Str(joined)) }
+            },
+
+            AggMethod::Sum => Self::numbers(values).into_iter()
+                .fold(None, |acc, nl| Some(match acc {
+                    None => nl,
+                    Some(mut total) => { total += nl; total },
+                }))
+                .map(MetaVal::from),
+
+            AggMethod::Average => {
+                let nums = Self::numbers(values);
+                let count = nums.len();
+
+                nums.into_iter()
+                    .fold(None, |acc, nl| Some(match acc {
+                        None => nl,
+                        Some(mut total) => { total += nl; total },
+                    }))
+                    .map(|total| {
+                        let sum_dec = match total {
+                            NumberLike::Integer(i) => BigDecimal::from(i),
+                            NumberLike::Decimal(d) => d,
+                        };
+
+                        MetaVal::Dec(sum_dec / BigDecimal::from(count as i64))
+                    })
+            },
+
+            AggMethod::Min => Self::numbers(values).into_iter()
+                .fold(None, |acc, nl| Some(match acc {
+                    None => nl,
+                    Some(best) => if nl.val_cmp(&best) == Ordering::Less { nl } else { best },
+                }))
+                .map(MetaVal::from),
+
+            AggMethod::Max => Self::numbers(values).into_iter()
+                .fold(None, |acc, nl| Some(match acc {
+                    None => nl,
+                    Some(best) => if nl.val_cmp(&best) == Ordering::Greater { nl } else { best },
+                }))
+                .map(MetaVal::from),
+        }
+    }
+
+    fn numbers<I>(values: I) -> Vec<NumberLike>
+    where
+        I: IntoIterator<Item = MetaVal>,
+    {
+        values.into_iter().filter_map(|mv| NumberLike::try_from(mv).ok()).collect()
+    }
+
+    fn as_str(value: MetaVal) -> Option<String> {
+        match value {
+            MetaVal::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
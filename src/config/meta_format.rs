@@ -0,0 +1,206 @@
+//! Defines the on-disk metadata file formats a library configuration can use.
+
+use std::collections::BTreeMap;
+
+use base64;
+use num_traits::ToPrimitive;
+use preserves::value::Value as PreservesValue;
+use preserves::value::NestedValue;
+use serde::Deserialize;
+
+use crate::metadata::types::MetaVal;
+
+#[derive(Debug)]
+pub enum Error {
+    Yaml(serde_yaml::Error),
+    Preserves(preserves::error::Error),
+    /// A Preserves value had a shape with no clean `MetaVal` equivalent
+    /// (e.g. a non-string-keyed dictionary).
+    Unrepresentable,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Yaml(err) => write!(f, "cannot parse YAML: {}", err),
+            Self::Preserves(err) => write!(f, "cannot parse Preserves: {}", err),
+            Self::Unrepresentable => write!(f, "Preserves value has no equivalent MetaVal representation"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Yaml(err) => Some(err),
+            Self::Preserves(err) => Some(err),
+            Self::Unrepresentable => None,
+        }
+    }
+}
+
+/// The on-disk format used to encode a metadata document.
+///
+/// YAML collapses several distinctions that Preserves keeps separate
+/// (symbol vs. string, integer vs. double, a dedicated byte string atom).
+/// Where a `MetaVal` has no dedicated slot for one of these (there is no
+/// `MetaVal::Bytes` in this tree), the mapping falls back to a base64-encoded
+/// `MetaVal::Str` rather than silently truncating data, and a Preserves
+/// record (label plus fields) is mapped onto a map with exactly two keys,
+/// `_label` (the record's label) and `_fields` (its fields as a sequence).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetaFormat {
+    Yaml,
+    Preserves,
+}
+
+impl Default for MetaFormat {
+    fn default() -> Self {
+        Self::Yaml
+    }
+}
+
+impl MetaFormat {
+    /// The file extension this format is conventionally stored under.
+    pub fn default_file_extension(&self) -> &'static str {
+        match self {
+            Self::Yaml => "yml",
+            Self::Preserves => "prs",
+        }
+    }
+
+    /// Parses a metadata document in this format into a `MetaVal`.
+    pub fn parse_str(&self, text: &str) -> Result<MetaVal, Error> {
+        match self {
+            Self::Yaml => serde_yaml::from_str(text).map_err(Error::Yaml),
+            Self::Preserves => {
+                let value: PreservesValue = preserves::value::text::from_str(text, preserves::value::Domain)
+                    .map_err(Error::Preserves)?;
+                preserves_value_to_meta_val(&value)
+            }
+        }
+    }
+
+    /// Serializes a `MetaVal` back into a metadata document in this format,
+    /// the write-side counterpart to `parse_str`.
+    ///
+    /// Round-tripping through `Preserves` is lossy in the same places
+    /// `parse_str` is: a symbol and a string both arrive as `MetaVal::Str`,
+    /// so both leave as a Preserves string, and a base64-encoded byte
+    /// string leaves as the same base64 text rather than a bytestring atom.
+    /// `MetaVal::Map` round-trips back into a record only when it carries
+    /// exactly the `_label`/`_fields` shape `parse_str` produces for one;
+    /// any other map becomes a plain Preserves dictionary.
+    pub fn write_str(&self, mv: &MetaVal) -> Result<String, Error> {
+        match self {
+            Self::Yaml => serde_yaml::to_string(mv).map_err(Error::Yaml),
+            Self::Preserves => {
+                let value = meta_val_to_preserves_value(mv);
+                preserves::value::text::to_string(&value, preserves::value::Domain)
+                    .map_err(Error::Preserves)
+            }
+        }
+    }
+}
+
+fn meta_val_to_preserves_value(mv: &MetaVal) -> PreservesValue {
+    match mv {
+        MetaVal::Bul(b) => PreservesValue::from(*b),
+        MetaVal::Int(i) => PreservesValue::from(*i),
+        MetaVal::Dec(d) => PreservesValue::from(d.to_f64().unwrap_or_default()),
+        MetaVal::Str(s) => PreservesValue::from(s.clone()),
+        MetaVal::Seq(items) => PreservesValue::from(
+            items.iter().map(meta_val_to_preserves_value).collect::<Vec<_>>()
+        ),
+        MetaVal::Map(map) => {
+            // Reverse the `_label`/`_fields` tagging `preserves_value_to_meta_val`
+            // applies to a record, if that's exactly what this map is.
+            if map.len() == 2 {
+                if let (Some(label), Some(MetaVal::Seq(fields))) = (map.get("_label"), map.get("_fields")) {
+                    let label_value = meta_val_to_preserves_value(label);
+                    let field_values = fields.iter().map(meta_val_to_preserves_value).collect::<Vec<_>>();
+                    return PreservesValue::record(label_value, field_values);
+                }
+            }
+
+            let dict: preserves::value::Map<PreservesValue, PreservesValue> = map.iter()
+                .map(|(k, v)| (PreservesValue::from(k.clone()), meta_val_to_preserves_value(v)))
+                .collect();
+            PreservesValue::from(dict)
+        },
+    }
+}
+
+fn preserves_value_to_meta_val(value: &PreservesValue) -> Result<MetaVal, Error> {
+    if let Some(b) = value.as_boolean() {
+        return Ok(MetaVal::Bul(b));
+    }
+    if let Some(i) = value.as_signed_integer() {
+        return Ok(MetaVal::Int(i));
+    }
+    if let Some(d) = value.as_double() {
+        return Ok(MetaVal::Dec(d.into()));
+    }
+    if let Some(s) = value.as_string() {
+        return Ok(MetaVal::Str(s.to_owned()));
+    }
+    if let Some(s) = value.as_symbol() {
+        // Symbols have no distinct `MetaVal` slot; preserve the text content.
+        return Ok(MetaVal::Str(s.to_owned()));
+    }
+    if let Some(bs) = value.as_bytestring() {
+        // No `MetaVal::Bytes` in this tree; keep every byte as base64 text
+        // (written back out as a string, not a bytestring).
+        return Ok(MetaVal::Str(base64::encode(bs)));
+    }
+    if let Some(seq) = value.as_sequence() {
+        let mut out = Vec::with_capacity(seq.len());
+        for item in seq {
+            out.push(preserves_value_to_meta_val(item)?);
+        }
+        return Ok(MetaVal::Seq(out));
+    }
+    if let Some(set) = value.as_set() {
+        // Sets have no ordering guarantees of their own; materialize as a
+        // deduplicated sequence in iteration order. Dedup happens after
+        // conversion to `MetaVal` (rather than on the source `Value`s),
+        // since distinct Preserves atoms (e.g. a symbol and a string with
+        // the same text) can collapse onto the same `MetaVal`.
+        let mut out: Vec<MetaVal> = Vec::with_capacity(set.len());
+        for item in set {
+            let mv = preserves_value_to_meta_val(item)?;
+
+            if !out.contains(&mv) {
+                out.push(mv);
+            }
+        }
+        return Ok(MetaVal::Seq(out));
+    }
+    if let Some(dict) = value.as_dictionary() {
+        let mut out = BTreeMap::new();
+        for (k, v) in dict {
+            let key = k.as_string().or_else(|| k.as_symbol())
+                .ok_or(Error::Unrepresentable)?
+                .to_owned();
+            out.insert(key, preserves_value_to_meta_val(v)?);
+        }
+        return Ok(MetaVal::Map(out));
+    }
+    if let Some(rec) = value.as_record() {
+        // A record is a label plus fields; store them under `_label` and
+        // `_fields` so the distinction from a plain dictionary survives the
+        // round trip.
+        let mut out = BTreeMap::new();
+        let label = preserves_value_to_meta_val(rec.label())?;
+        let mut fields = Vec::with_capacity(rec.fields().len());
+        for field in rec.fields() {
+            fields.push(preserves_value_to_meta_val(field)?);
+        }
+        out.insert("_label".to_owned(), label);
+        out.insert("_fields".to_owned(), MetaVal::Seq(fields));
+        return Ok(MetaVal::Map(out));
+    }
+
+    Err(Error::Unrepresentable)
+}
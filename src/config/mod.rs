@@ -1,5 +1,6 @@
 //! Provides configuration options for a library, both programmatically and via config files.
 
+pub mod agg_method;
 pub mod fallback_method;
 pub mod meta_format;
 pub mod selection;
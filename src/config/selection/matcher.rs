@@ -1,18 +1,22 @@
 //! Represents a method of determining whether a potential item path is to be
 //! included in metadata lookup.
 
+use std::collections::HashMap;
 use std::path::Path;
+use std::path::PathBuf;
 use std::convert::TryFrom;
 
-use globset::Glob;
+use globset::GlobBuilder;
 use globset::GlobSet;
 use globset::GlobSetBuilder;
 use globset::Error as GlobError;
+use serde::Deserialize;
 
 #[derive(Debug)]
 pub enum Error {
     InvalidPattern(GlobError),
     BuildFailure(GlobError),
+    UnknownType(String),
 }
 
 impl std::fmt::Display for Error {
@@ -20,6 +24,7 @@ impl std::fmt::Display for Error {
         match self {
             Self::InvalidPattern(ref err) => write!(f, "invalid pattern: {}", err),
             Self::BuildFailure(ref err) => write!(f, "cannot build matcher: {}", err),
+            Self::UnknownType(ref name) => write!(f, "unknown file type: {}", name),
         }
     }
 }
@@ -29,58 +34,306 @@ impl std::error::Error for Error {
         match self {
             Self::InvalidPattern(ref err) => Some(err),
             Self::BuildFailure(ref err) => Some(err),
+            Self::UnknownType(_) => None,
         }
     }
 }
 
+/// A registry mapping a named file-type alias (e.g. `audio`) to the set of
+/// glob patterns it stands for, so a `Matcher` pattern can reference
+/// `type:audio` instead of repeating the same globs across every selection.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct TypeDefs(HashMap<String, Vec<String>>);
+
+impl TypeDefs {
+    /// Returns an empty registry, with no type aliases defined.
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Returns a registry preloaded with a handful of common file-type
+    /// aliases, mirroring the `ignore` crate's built-in type definitions.
+    pub fn defaults() -> Self {
+        let mut types = Self::new();
+        types.add("audio", ["*.flac", "*.mp3", "*.ogg", "*.wav", "*.m4a"].iter().copied());
+        types.add("image", ["*.png", "*.jpg", "*.jpeg", "*.gif", "*.bmp", "*.tiff"].iter().copied());
+        types.add("video", ["*.mp4", "*.mkv", "*.avi", "*.mov", "*.webm"].iter().copied());
+        types
+    }
+
+    /// Registers `globs` under `name`, replacing any prior definition for
+    /// that name.
+    pub fn add<S, II, G>(&mut self, name: S, globs: II)
+    where
+        S: Into<String>,
+        II: IntoIterator<Item = G>,
+        G: Into<String>,
+    {
+        self.0.insert(name.into(), globs.into_iter().map(Into::into).collect());
+    }
+
+    /// Merges `other`'s definitions into this registry. Where both define
+    /// the same name, `other`'s definition wins, so project config can
+    /// override the built-in defaults.
+    pub fn merge(&mut self, other: TypeDefs) {
+        self.0.extend(other.0);
+    }
+
+    /// Returns the globs registered under `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.0.get(name).map(Vec::as_slice)
+    }
+}
+
+impl Default for TypeDefs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-pattern knobs forwarded to `globset::GlobBuilder` when compiling a
+/// `Matcher`, instead of the bare defaults `Glob::new` uses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatcherOptions {
+    /// Lets e.g. `*.FLAC` match `music.flac`.
+    pub case_insensitive: bool,
+    /// Stops `*` and `?` from matching `/`, so a pattern like `a/*/b`
+    /// doesn't silently cross directory boundaries.
+    pub literal_separator: bool,
+    pub backslash_escape: bool,
+}
+
 #[derive(Deserialize)]
 #[serde(untagged)]
-enum OneOrManyPatterns {
+pub(crate) enum MatcherRepr {
+    Any,
+    Empty,
     One(String),
     Many(Vec<String>),
+    WithOptions {
+        patterns: Vec<String>,
+        #[serde(default)]
+        case_insensitive: bool,
+        #[serde(default)]
+        literal_separator: bool,
+        #[serde(default)]
+        backslash_escape: bool,
+    },
 }
 
-impl TryFrom<OneOrManyPatterns> for Matcher {
+impl TryFrom<MatcherRepr> for Matcher {
     type Error = Error;
 
-    fn try_from(oom: OneOrManyPatterns) -> Result<Self, Self::Error> {
-        match oom {
-            OneOrManyPatterns::One(p) => Self::build(&[p]),
-            OneOrManyPatterns::Many(ps) => Self::build(&ps),
+    fn try_from(repr: MatcherRepr) -> Result<Self, Self::Error> {
+        match repr {
+            MatcherRepr::Any => Ok(Self::any()),
+            MatcherRepr::Empty => Ok(Self::empty()),
+            MatcherRepr::One(p) => Self::build(&[p]),
+            MatcherRepr::Many(ps) => Self::build(&ps),
+            MatcherRepr::WithOptions { patterns, case_insensitive, literal_separator, backslash_escape } => {
+                Self::build_with(&patterns, MatcherOptions { case_insensitive, literal_separator, backslash_escape })
+            }
         }
     }
 }
 
-/// Filter for file paths that uses zero or more glob patterns to perform matching.
+/// Selects which part of a candidate path `Matcher::is_match` tests against
+/// its patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchScope {
+    /// Only the path's file name is tested, so a pattern can never see
+    /// which directory a file lives in (e.g. `a/**/b` can never match).
+    FileName,
+    /// The whole path is tested, after stripping the matcher's configured
+    /// base and normalizing separators to `/`. This is what lets a pattern
+    /// like `assets/*.png` key off directory structure.
+    FullPath,
+}
+
+impl Default for MatchScope {
+    fn default() -> Self {
+        Self::FileName
+    }
+}
+
+/// Filter for file paths that uses zero or more glob patterns to perform
+/// matching, with gitignore-style override semantics: a pattern prefixed
+/// with `!` whitelists paths it matches, and when a path is matched by more
+/// than one pattern, the pattern added last (not the most permissive one)
+/// decides the outcome. A pattern of the form `type:name` is expanded into
+/// the globs registered under `name` in a `TypeDefs` registry.
 #[derive(Debug, Deserialize)]
-#[serde(try_from = "OneOrManyPatterns")]
-pub struct Matcher(GlobSet);
+#[serde(try_from = "MatcherRepr")]
+pub struct Matcher {
+    positive: GlobSet,
+    positive_indices: Vec<usize>,
+    negated: GlobSet,
+    negated_indices: Vec<usize>,
+    #[serde(skip)]
+    match_scope: MatchScope,
+    #[serde(skip)]
+    base: PathBuf,
+}
 
 impl Matcher {
-    /// Attempts to build a matcher out of an iterable of string-likes.
+    /// Attempts to build a matcher out of an iterable of string-likes, using
+    /// `GlobBuilder`'s default options for each pattern.
     pub fn build<II, S>(pattern_strs: II) -> Result<Self, Error>
     where
         II: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        let mut builder = GlobSetBuilder::new();
+        Self::build_with(pattern_strs, MatcherOptions::default())
+    }
+
+    /// Like `build`, but routes each pattern through `GlobBuilder` with
+    /// `options` applied before compiling it, and expands `type:` sigils
+    /// using the built-in `TypeDefs` defaults.
+    ///
+    /// A pattern prefixed with `!` is compiled as a whitelist (un-ignore)
+    /// rule rather than an ordinary inclusion rule; see `is_match` for how
+    /// overrides are resolved when a path matches more than one pattern.
+    pub fn build_with<II, S>(pattern_strs: II, options: MatcherOptions) -> Result<Self, Error>
+    where
+        II: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::build_with_types(pattern_strs, options, &TypeDefs::defaults())
+    }
+
+    /// Like `build_with`, but resolves any `type:name` pattern (e.g.
+    /// `type:audio`) against `types` rather than the built-in defaults,
+    /// expanding it into the globs registered under that name. All globs
+    /// produced by a single `type:name` pattern share that pattern's
+    /// original position for override resolution in `is_match`.
+    pub fn build_with_types<II, S>(
+        pattern_strs: II,
+        options: MatcherOptions,
+        types: &TypeDefs,
+    ) -> Result<Self, Error>
+    where
+        II: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut positive_builder = GlobSetBuilder::new();
+        let mut positive_indices = Vec::new();
+        let mut negated_builder = GlobSetBuilder::new();
+        let mut negated_indices = Vec::new();
 
-        for pattern_str in pattern_strs.into_iter() {
+        for (index, pattern_str) in pattern_strs.into_iter().enumerate() {
             let pattern_str = pattern_str.as_ref();
-            let pattern = Glob::new(&pattern_str).map_err(Error::InvalidPattern)?;
-            builder.add(pattern);
+            let (negated, pattern_str) = match pattern_str.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, pattern_str),
+            };
+
+            for expanded in Self::expand_pattern(pattern_str, types)? {
+                let pattern = GlobBuilder::new(expanded)
+                    .case_insensitive(options.case_insensitive)
+                    .literal_separator(options.literal_separator)
+                    .backslash_escape(options.backslash_escape)
+                    .build()
+                    .map_err(Error::InvalidPattern)?;
+
+                if negated {
+                    negated_builder.add(pattern);
+                    negated_indices.push(index);
+                } else {
+                    positive_builder.add(pattern);
+                    positive_indices.push(index);
+                }
+            }
         }
 
-        let matcher = builder.build().map_err(Error::BuildFailure)?;
+        Ok(Self {
+            positive: positive_builder.build().map_err(Error::BuildFailure)?,
+            positive_indices,
+            negated: negated_builder.build().map_err(Error::BuildFailure)?,
+            negated_indices,
+            match_scope: MatchScope::default(),
+            base: PathBuf::new(),
+        })
+    }
+
+    /// Expands a `type:name` pattern into the globs registered under `name`
+    /// in `types`, or passes any other pattern through unchanged.
+    fn expand_pattern<'a>(pattern_str: &'a str, types: &'a TypeDefs) -> Result<Vec<&'a str>, Error> {
+        match pattern_str.strip_prefix("type:") {
+            Some(type_name) => {
+                types.get(type_name)
+                    .map(|globs| globs.iter().map(String::as_str).collect())
+                    .ok_or_else(|| Error::UnknownType(type_name.to_string()))
+            },
+            None => Ok(vec![pattern_str]),
+        }
+    }
+
+    /// Switches which part of a candidate path `is_match` tests against
+    /// patterns. Default is `MatchScope::FileName`.
+    pub fn with_match_scope(mut self, match_scope: MatchScope) -> Self {
+        self.match_scope = match_scope;
+        self
+    }
 
-        Ok(Self(matcher))
+    /// Sets the base path stripped from a candidate before matching in
+    /// `MatchScope::FullPath` mode. Has no effect in `MatchScope::FileName`
+    /// mode. Default is empty, i.e. no stripping.
+    pub fn with_base<P: Into<PathBuf>>(mut self, base: P) -> Self {
+        self.base = base.into();
+        self
     }
 
-    /// Matches a path based on its file name. If the path does not have a file
-    /// name (e.g. '/' on Unix systems), returns `false`.
+    /// Matches a path according to `match_scope`: either just its file name
+    /// (e.g. '/' on Unix systems has none, so it always returns `false`), or
+    /// the full path, with `base` stripped and separators normalized to
+    /// `/`, so a path equal to `base` also returns `false`.
+    ///
+    /// If both a plain and a `!`-prefixed pattern match, the one added last
+    /// (the one with the greater original insertion index) wins, mirroring
+    /// gitignore's last-match-wins behavior. A path that matches nothing
+    /// returns `false`.
     pub fn is_match<P: AsRef<Path>>(&self, path: P) -> bool {
-        // Matching on only file name is needed for patterns such as "self*".
-        path.as_ref().file_name().map(|f| self.0.is_match(f)).unwrap_or(false)
+        match self.match_scope {
+            MatchScope::FileName => {
+                // Matching on only file name is needed for patterns such as "self*".
+                match path.as_ref().file_name() {
+                    Some(file_name) => self.is_match_globs(file_name),
+                    None => false,
+                }
+            },
+            MatchScope::FullPath => {
+                let relative = path.as_ref().strip_prefix(&self.base).unwrap_or_else(|_| path.as_ref());
+                let normalized = relative.to_string_lossy().replace('\\', "/");
+
+                if normalized.is_empty() {
+                    false
+                }
+                else {
+                    self.is_match_globs(Path::new(&normalized))
+                }
+            },
+        }
+    }
+
+    fn is_match_globs<P: AsRef<Path>>(&self, candidate: P) -> bool {
+        let candidate = candidate.as_ref();
+
+        let last_positive = self.positive.matches(candidate)
+            .into_iter()
+            .map(|i| self.positive_indices[i])
+            .max();
+        let last_negated = self.negated.matches(candidate)
+            .into_iter()
+            .map(|i| self.negated_indices[i])
+            .max();
+
+        match (last_positive, last_negated) {
+            (None, None) => false,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some(p), Some(n)) => p > n,
+        }
     }
 
     /// Returns a matcher that matches any path that has a file name.
@@ -91,7 +344,14 @@ impl Matcher {
 
     /// Returns a matcher that matches no paths.
     pub fn empty() -> Self {
-        Self(GlobSet::empty())
+        Self {
+            positive: GlobSet::empty(),
+            positive_indices: Vec::new(),
+            negated: GlobSet::empty(),
+            negated_indices: Vec::new(),
+            match_scope: MatchScope::default(),
+            base: PathBuf::new(),
+        }
     }
 }
 
@@ -151,6 +411,158 @@ mod tests {
         assert!(Matcher::build(&["*.a\\"]).is_err());
     }
 
+    #[test]
+    fn test_build_with_case_insensitive() {
+        let matcher = Matcher::build(&["*.flac"]).unwrap();
+        assert_eq!(matcher.is_match("music.FLAC"), false);
+
+        let matcher = Matcher::build_with(
+            &["*.flac"],
+            MatcherOptions { case_insensitive: true, ..Default::default() },
+        ).unwrap();
+        assert_eq!(matcher.is_match("music.FLAC"), true);
+        assert_eq!(matcher.is_match("music.flac"), true);
+    }
+
+    #[test]
+    fn test_build_with_literal_separator() {
+        // `Matcher::is_match` tests only a path's file name by default, so
+        // exercise the underlying `GlobSet` directly against full paths to
+        // see `literal_separator`'s effect on `*` crossing `/`.
+        let matcher = Matcher::build(&["a/*/b"]).unwrap();
+        assert_eq!(matcher.positive.is_match(Path::new("a/x/y/b")), true);
+
+        let matcher = Matcher::build_with(
+            &["a/*/b"],
+            MatcherOptions { literal_separator: true, ..Default::default() },
+        ).unwrap();
+        assert_eq!(matcher.positive.is_match(Path::new("a/x/b")), true);
+        assert_eq!(matcher.positive.is_match(Path::new("a/x/y/b")), false);
+    }
+
+    #[test]
+    fn test_override_negation() {
+        let matcher = Matcher::build(&["*.flac", "!sample_*.flac"]).unwrap();
+        assert_eq!(matcher.is_match("music.flac"), true);
+        assert_eq!(matcher.is_match("sample_music.flac"), false);
+        assert_eq!(matcher.is_match("music.mp3"), false);
+    }
+
+    #[test]
+    fn test_override_last_match_wins() {
+        // A later plain pattern re-includes what an earlier `!` pattern
+        // whitelisted, since the last matching pattern decides.
+        let matcher = Matcher::build(
+            &["*.flac", "!sample_*.flac", "sample_keep.flac"],
+        ).unwrap();
+        assert_eq!(matcher.is_match("sample_music.flac"), false);
+        assert_eq!(matcher.is_match("sample_keep.flac"), true);
+    }
+
+    #[test]
+    fn test_override_no_match_defaults_false() {
+        let matcher = Matcher::build(&["!*.flac"]).unwrap();
+        assert_eq!(matcher.is_match("music.mp3"), false);
+        assert_eq!(matcher.is_match("music.flac"), false);
+    }
+
+    #[test]
+    fn test_type_defs_add_and_get() {
+        let mut types = TypeDefs::new();
+        assert_eq!(types.get("audio"), None);
+
+        types.add("audio", vec!["*.flac".to_string(), "*.mp3".to_string()]);
+        assert_eq!(types.get("audio"), Some(&["*.flac".to_string(), "*.mp3".to_string()][..]));
+    }
+
+    #[test]
+    fn test_type_defs_merge_overrides() {
+        let mut types = TypeDefs::new();
+        types.add("audio", vec!["*.flac".to_string()]);
+
+        let mut overrides = TypeDefs::new();
+        overrides.add("audio", vec!["*.wav".to_string()]);
+        types.merge(overrides);
+
+        assert_eq!(types.get("audio"), Some(&["*.wav".to_string()][..]));
+    }
+
+    #[test]
+    fn test_build_with_type_sigil() {
+        let matcher = Matcher::build(&["type:audio"]).unwrap();
+        assert_eq!(matcher.is_match("music.flac"), true);
+        assert_eq!(matcher.is_match("music.mp3"), true);
+        assert_eq!(matcher.is_match("photo.png"), false);
+    }
+
+    #[test]
+    fn test_build_with_types_custom_registry() {
+        let mut types = TypeDefs::new();
+        types.add("lossless", vec!["*.flac".to_string(), "*.wav".to_string()]);
+
+        let matcher = Matcher::build_with_types(
+            &["type:lossless"],
+            MatcherOptions::default(),
+            &types,
+        ).unwrap();
+        assert_eq!(matcher.is_match("music.flac"), true);
+        assert_eq!(matcher.is_match("music.mp3"), false);
+    }
+
+    #[test]
+    fn test_build_with_unknown_type_sigil_errs() {
+        assert!(Matcher::build(&["type:nonexistent"]).is_err());
+    }
+
+    #[test]
+    fn test_match_scope_file_name_ignores_directories() {
+        let matcher = Matcher::build(&["assets/*.png"]).unwrap().with_match_scope(MatchScope::FileName);
+        assert_eq!(matcher.is_match("assets/logo.png"), false);
+    }
+
+    #[test]
+    fn test_match_scope_full_path() {
+        let matcher = Matcher::build_with(
+            &["assets/*.png"],
+            MatcherOptions { literal_separator: true, ..Default::default() },
+        ).unwrap().with_match_scope(MatchScope::FullPath);
+
+        assert_eq!(matcher.is_match("assets/logo.png"), true);
+        assert_eq!(matcher.is_match("other/logo.png"), false);
+        assert_eq!(matcher.is_match("assets/sub/logo.png"), false);
+    }
+
+    #[test]
+    fn test_match_scope_full_path_with_base_stripped() {
+        let matcher = Matcher::build_with(
+            &["assets/*.png"],
+            MatcherOptions { literal_separator: true, ..Default::default() },
+        ).unwrap()
+            .with_match_scope(MatchScope::FullPath)
+            .with_base("/library/root");
+
+        assert_eq!(matcher.is_match("/library/root/assets/logo.png"), true);
+        assert_eq!(matcher.is_match("/library/root/other/logo.png"), false);
+    }
+
+    #[test]
+    fn test_match_scope_full_path_no_usable_components() {
+        let matcher = Matcher::build(&["*"]).unwrap()
+            .with_match_scope(MatchScope::FullPath)
+            .with_base("/library/root");
+
+        assert_eq!(matcher.is_match("/library/root"), false);
+    }
+
+    #[test]
+    fn test_deserialize_with_options() {
+        let text = "patterns: ['*.flac']\ncase_insensitive: true";
+        let matcher: Matcher = serde_yaml::from_str(&text).unwrap();
+
+        assert_eq!(matcher.is_match("music.FLAC"), true);
+        assert_eq!(matcher.is_match("music.mp3"), false);
+    }
+
     #[test]
     fn test_is_match() {
         let matcher = Matcher::build(&["*.a", "*.b"]).unwrap();
@@ -1,5 +1,8 @@
 mod matcher;
+mod rules;
 
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
 use std::convert::{TryFrom, TryInto};
 use std::fs::ReadDir;
 use std::io::Result as IoResult;
@@ -10,23 +13,81 @@ use serde::Deserialize;
 
 use crate::config::Sorter;
 
-pub use self::matcher::{Error as MatcherError, Matcher};
+pub use self::matcher::{Error as MatcherError, Matcher, MatcherOptions, MatchScope, TypeDefs};
 pub(crate) use self::matcher::MatcherRepr;
+pub use self::rules::RuleSet;
 
 enum FileOrDir {
     File,
     Dir,
 }
 
-/// A type that represents included and excluded item files and directories.
 #[derive(Debug)]
-pub struct Selection {
-    include_files: Matcher,
-    exclude_files: Matcher,
-    include_dirs: Matcher,
-    exclude_dirs: Matcher,
+enum Backend {
+    /// Strictly "matches include AND NOT matches exclude", for files and
+    /// directories independently.
+    FourWay {
+        include_files: Matcher,
+        exclude_files: Matcher,
+        include_dirs: Matcher,
+        exclude_dirs: Matcher,
+    },
+
+    /// An ordered, gitignore-style rule list with whitelist support, applied
+    /// the same way to both files and directories.
+    Ordered(RuleSet),
+
+    /// A base `Selection`, further restricted by ignore files discovered
+    /// under `root`, each scoped to the directory that contains it.
+    Stacked {
+        base: Box<Selection>,
+        root: PathBuf,
+        scoped: BTreeMap<PathBuf, RuleSet>,
+    },
+}
+
+/// Error produced while discovering and parsing ignore files for
+/// `Selection::with_ignore_files`.
+#[derive(Debug)]
+pub enum IgnoreFileError {
+    Io(std::io::Error),
+    Pattern(MatcherError),
+}
+
+impl std::fmt::Display for IgnoreFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot read ignore files: {}", err),
+            Self::Pattern(err) => write!(f, "invalid pattern in ignore file: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for IgnoreFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Pattern(err) => Some(err),
+        }
+    }
 }
 
+impl From<std::io::Error> for IgnoreFileError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<MatcherError> for IgnoreFileError {
+    fn from(err: MatcherError) -> Self {
+        Self::Pattern(err)
+    }
+}
+
+/// A type that represents included and excluded item files and directories.
+#[derive(Debug)]
+pub struct Selection(Backend);
+
 impl Default for Selection {
     fn default() -> Self {
         let include_files = Matcher::any();
@@ -45,12 +106,65 @@ impl Selection {
         include_dirs: Matcher,
         exclude_dirs: Matcher,
     ) -> Self {
-        Self {
+        Self(Backend::FourWay {
             include_files,
             exclude_files,
             include_dirs,
             exclude_dirs,
+        })
+    }
+
+    /// Creates a `Selection` backed by an ordered, gitignore-style rule list
+    /// instead of the four-matcher include/exclude form.
+    pub fn from_rules(rules: RuleSet) -> Self {
+        Self(Backend::Ordered(rules))
+    }
+
+    /// Layers ignore files discovered under `root` on top of this
+    /// `Selection`. Every directory in the subtree that contains a file
+    /// named `filename` contributes a rule set scoped to that directory, so
+    /// a pattern in `sub/.akashicignore` only ever affects paths under
+    /// `sub/`. A path must pass both this `Selection` and the most specific
+    /// applicable ignore file to be selected.
+    pub fn with_ignore_files(self, root: &Path, filename: &str) -> Result<Self, IgnoreFileError> {
+        let scoped = Self::discover_ignore_files(root, filename)?;
+
+        Ok(Self(Backend::Stacked {
+            base: Box::new(self),
+            root: root.to_owned(),
+            scoped,
+        }))
+    }
+
+    fn discover_ignore_files(
+        root: &Path,
+        filename: &str,
+    ) -> Result<BTreeMap<PathBuf, RuleSet>, IgnoreFileError> {
+        let mut scoped = BTreeMap::new();
+        let mut dir_stack = vec![root.to_owned()];
+
+        while let Some(dir) = dir_stack.pop() {
+            let ignore_path = dir.join(filename);
+
+            if ignore_path.is_file() {
+                let contents = std::fs::read_to_string(&ignore_path)?;
+                let patterns = contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+                scoped.insert(dir.clone(), RuleSet::build(patterns)?);
+            }
+
+            for entry in dir.read_dir()? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    dir_stack.push(entry.path());
+                }
+            }
         }
+
+        Ok(scoped)
     }
 
     pub fn from_patterns<'a, IA, SA, IB, SB, IC, SC, ID, SD>(
@@ -83,12 +197,42 @@ impl Selection {
     }
 
     fn is_pattern_match<P: AsRef<Path>>(&self, path: &P, fod: FileOrDir) -> bool {
-        let (inc, exc) = match fod {
-            FileOrDir::File => (&self.include_files, &self.exclude_files),
-            FileOrDir::Dir => (&self.include_dirs, &self.exclude_dirs),
-        };
+        match &self.0 {
+            Backend::FourWay { include_files, exclude_files, include_dirs, exclude_dirs } => {
+                let (inc, exc) = match fod {
+                    FileOrDir::File => (include_files, exclude_files),
+                    FileOrDir::Dir => (include_dirs, exclude_dirs),
+                };
+
+                inc.is_match(&path) && !exc.is_match(&path)
+            }
+            Backend::Ordered(rule_set) => rule_set.is_selected(path.as_ref()),
+
+            Backend::Stacked { base, scoped, .. } => {
+                if !base.is_pattern_match(path, fod) {
+                    return false;
+                }
 
-        inc.is_match(&path) && !exc.is_match(&path)
+                // Walk up from the path's parent directory toward the root,
+                // and defer to the most specific ignore file that covers it.
+                let path_ref = path.as_ref();
+                let mut dir = path_ref.parent();
+
+                while let Some(d) = dir {
+                    if let Some(rule_set) = scoped.get(d) {
+                        if let Ok(relative) = path_ref.strip_prefix(d) {
+                            if let Some(verdict) = rule_set.verdict_for(relative) {
+                                return verdict;
+                            }
+                        }
+                    }
+
+                    dir = d.parent();
+                }
+
+                true
+            }
+        }
     }
 
     /// Returns true if the path matches according to the file matcher.
@@ -110,16 +254,34 @@ impl Selection {
     }
 
     /// Returns true if a path is selected.
-    /// This accesses the filesystem to tell if the path is a file or directory.
+    /// This accesses the filesystem to tell if the path is a file or directory,
+    /// but only when the file and directory verdicts actually disagree.
     pub fn is_selected<P: AsRef<Path>>(&self, path: &P) -> IoResult<bool> {
-        let file_info = std::fs::metadata(&path)?;
+        self.is_selected_with(path, || Ok(std::fs::metadata(&path)?.is_dir()))
+    }
 
-        Ok(if file_info.is_file() {
-            self.is_file_pattern_match(path)
-        } else if file_info.is_dir() {
-            self.is_dir_pattern_match(path)
+    /// Like `is_selected`, but the caller supplies a closure that determines
+    /// whether the path is a directory, instead of this method always
+    /// stat-ing the path itself.
+    ///
+    /// The lexical file and directory verdicts are computed first; if they
+    /// agree, that verdict is returned immediately and `is_dir` is never
+    /// called. This lets callers that already know the entry type (e.g. from
+    /// `DirEntry::file_type()` during a walk) avoid a redundant syscall.
+    pub fn is_selected_with<P, F>(&self, path: &P, is_dir: F) -> IoResult<bool>
+    where
+        P: AsRef<Path>,
+        F: FnOnce() -> IoResult<bool>,
+    {
+        let file_verdict = self.is_file_pattern_match(path);
+        let dir_verdict = self.is_dir_pattern_match(path);
+
+        Ok(if file_verdict == dir_verdict {
+            file_verdict
+        } else if is_dir()? {
+            dir_verdict
         } else {
-            false
+            file_verdict
         })
     }
 
@@ -146,11 +308,52 @@ impl Selection {
 
         Ok(res_paths)
     }
+
+    /// Selects files inside a directory and all of its subdirectories that
+    /// match this `Selection`. Subtrees rooted at a directory that fails the
+    /// directory matcher are pruned before they are ever read, so excluded
+    /// trees never incur a `read_dir` call.
+    pub fn select_in_dir_recursive(&self, dir_path: &Path) -> IoResult<RecursiveSelectedSubPaths> {
+        let dir_reader = dir_path.read_dir()?;
+
+        Ok(RecursiveSelectedSubPaths {
+            stack: vec![dir_reader],
+            selection: &self,
+        })
+    }
+
+    /// Selects files inside a directory and all of its subdirectories that
+    /// match this `Selection`, sorting each directory level with `sorter`
+    /// before descending into it.
+    pub fn select_in_dir_recursive_sorted<'a, 's>(
+        &'a self,
+        dir_path: &Path,
+        sorter: &'s Sorter,
+    ) -> IoResult<SortedRecursiveSelectedSubPaths<'a, 's>> {
+        let first_frame = Self::read_sorted_dir(dir_path, sorter)?;
+
+        Ok(SortedRecursiveSelectedSubPaths {
+            stack: vec![first_frame],
+            selection: &self,
+            sorter,
+        })
+    }
+
+    fn read_sorted_dir(dir_path: &Path, sorter: &Sorter) -> IoResult<VecDeque<PathBuf>> {
+        let mut res_paths = dir_path
+            .read_dir()?
+            .map(|res| res.map(|dir_entry| dir_entry.path()))
+            .collect::<Vec<_>>();
+
+        sorter.sort_path_results(&mut res_paths);
+
+        res_paths.into_iter().collect::<IoResult<VecDeque<_>>>()
+    }
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(default, deny_unknown_fields)]
-pub(crate) struct SelectionRepr {
+pub(crate) struct FourWaySelectionRepr {
     pub exclude_sources: bool,
     pub include_files: MatcherRepr,
     pub exclude_files: MatcherRepr,
@@ -158,7 +361,7 @@ pub(crate) struct SelectionRepr {
     pub exclude_dirs: MatcherRepr,
 }
 
-impl Default for SelectionRepr {
+impl Default for FourWaySelectionRepr {
     fn default() -> Self {
         Self {
             exclude_sources: true,
@@ -170,16 +373,42 @@ impl Default for SelectionRepr {
     }
 }
 
+/// The ordered, gitignore-style alternative to `FourWaySelectionRepr`: a
+/// single rule list where a leading `!` whitelists and a leading `/` anchors.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct OrderedSelectionRepr {
+    pub rules: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum SelectionRepr {
+    FourWay(FourWaySelectionRepr),
+    Ordered(OrderedSelectionRepr),
+}
+
+impl Default for SelectionRepr {
+    fn default() -> Self {
+        Self::FourWay(FourWaySelectionRepr::default())
+    }
+}
+
 impl TryFrom<SelectionRepr> for Selection {
     type Error = MatcherError;
 
     fn try_from(value: SelectionRepr) -> Result<Self, Self::Error> {
-        Ok(Self {
-            include_files: value.include_files.try_into()?,
-            exclude_files: value.exclude_files.try_into()?,
-            include_dirs: value.include_dirs.try_into()?,
-            exclude_dirs: value.exclude_dirs.try_into()?,
-        })
+        match value {
+            SelectionRepr::FourWay(four_way) => Ok(Self::new(
+                four_way.include_files.try_into()?,
+                four_way.exclude_files.try_into()?,
+                four_way.include_dirs.try_into()?,
+                four_way.exclude_dirs.try_into()?,
+            )),
+            SelectionRepr::Ordered(ordered) => {
+                Ok(Self::from_rules(RuleSet::build(&ordered.rules)?))
+            }
+        }
     }
 }
 
@@ -197,7 +426,13 @@ impl<'a> Iterator for SelectedSubPaths<'a> {
         read_dir.find_map(|res| match res {
             Ok(dir_entry) => {
                 let sub_path = dir_entry.path();
-                match selection.is_selected(&sub_path) {
+                // The entry already knows its own type, so use that instead of
+                // stat-ing the sub-path again inside `is_selected`.
+                let verdict = selection.is_selected_with(&sub_path, || {
+                    Ok(dir_entry.file_type()?.is_dir())
+                });
+
+                match verdict {
                     Ok(true) => Some(Ok(sub_path)),
                     Ok(false) => None,
                     Err(err) => Some(Err(err)),
@@ -208,6 +443,93 @@ impl<'a> Iterator for SelectedSubPaths<'a> {
     }
 }
 
+/// Selects files in a directory and all of its subdirectories. A directory
+/// subtree is pruned (never `read_dir`'d) as soon as it fails the directory
+/// matcher, so excluded trees are never materialized.
+pub struct RecursiveSelectedSubPaths<'a> {
+    stack: Vec<ReadDir>,
+    selection: &'a Selection,
+}
+
+impl<'a> Iterator for RecursiveSelectedSubPaths<'a> {
+    type Item = IoResult<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let read_dir = self.stack.last_mut()?;
+
+            match read_dir.next() {
+                None => {
+                    // This level is exhausted, pop back up to its parent.
+                    self.stack.pop();
+                }
+                Some(Err(err)) => return Some(Err(err)),
+                Some(Ok(dir_entry)) => {
+                    let sub_path = dir_entry.path();
+
+                    let is_dir = match dir_entry.file_type() {
+                        Ok(ft) => ft.is_dir(),
+                        Err(err) => return Some(Err(err)),
+                    };
+
+                    if is_dir {
+                        // Only descend if this subtree is not pruned.
+                        if self.selection.is_dir_pattern_match(&sub_path) {
+                            match sub_path.read_dir() {
+                                Ok(child_reader) => self.stack.push(child_reader),
+                                Err(err) => return Some(Err(err)),
+                            }
+                        }
+                    } else if self.selection.is_file_pattern_match(&sub_path) {
+                        return Some(Ok(sub_path));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Like `RecursiveSelectedSubPaths`, but each directory level is sorted with
+/// a `Sorter` before its entries are yielded or descended into.
+pub struct SortedRecursiveSelectedSubPaths<'a, 's> {
+    stack: Vec<VecDeque<PathBuf>>,
+    selection: &'a Selection,
+    sorter: &'s Sorter,
+}
+
+impl<'a, 's> Iterator for SortedRecursiveSelectedSubPaths<'a, 's> {
+    type Item = IoResult<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+
+            match frame.pop_front() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(sub_path) => {
+                    let is_dir = match std::fs::metadata(&sub_path) {
+                        Ok(meta) => meta.is_dir(),
+                        Err(err) => return Some(Err(err)),
+                    };
+
+                    if is_dir {
+                        if self.selection.is_dir_pattern_match(&sub_path) {
+                            match Selection::read_sorted_dir(&sub_path, self.sorter) {
+                                Ok(child_frame) => self.stack.push(child_frame),
+                                Err(err) => return Some(Err(err)),
+                            }
+                        }
+                    } else if self.selection.is_file_pattern_match(&sub_path) {
+                        return Some(Ok(sub_path));
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,6 +625,19 @@ mod tests {
         assert_eq!(selection.is_file_pattern_match(&"path/to/music.ogg"), false);
     }
 
+    #[test]
+    fn deserialization_ordered() {
+        let text = r#"
+            rules = ["*.log", "!keep.log"]
+        "#;
+        let selection_repr: SelectionRepr = toml::from_str(&text).unwrap();
+        let selection: Selection = selection_repr.try_into().unwrap();
+
+        assert_eq!(selection.is_file_pattern_match(&"cache/debug.log"), false);
+        assert_eq!(selection.is_file_pattern_match(&"cache/keep.log"), true);
+        assert_eq!(selection.is_file_pattern_match(&"cache/other.yml"), true);
+    }
+
     #[test]
     fn is_pattern_match() {
         let selection = Selection::new(
@@ -360,6 +695,101 @@ mod tests {
         assert_eq!(selection.is_file_pattern_match(&"path/to/music.ogg"), false);
     }
 
+    #[test]
+    fn is_selected_with() {
+        let selection = Selection::new(
+            Matcher::build(&["*.flac"]).unwrap(),
+            Matcher::empty(),
+            Matcher::any(),
+            Matcher::empty(),
+        );
+
+        // File and directory verdicts agree (both true), closure is never called.
+        assert_eq!(
+            selection
+                .is_selected_with(&"music.flac", || panic!("should not stat"))
+                .unwrap(),
+            true,
+        );
+
+        // File and directory verdicts disagree, closure is consulted.
+        assert_eq!(
+            selection.is_selected_with(&"photo.png", || Ok(false)).unwrap(),
+            false,
+        );
+        assert_eq!(
+            selection.is_selected_with(&"photo.png", || Ok(true)).unwrap(),
+            true,
+        );
+    }
+
+    #[test]
+    fn select_in_dir_recursive() {
+        let root = std::env::temp_dir().join("akashic_test_select_in_dir_recursive");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("keep/nested")).unwrap();
+        std::fs::create_dir_all(root.join("skip/nested")).unwrap();
+
+        std::fs::write(root.join("top.flac"), b"").unwrap();
+        std::fs::write(root.join("keep/a.flac"), b"").unwrap();
+        std::fs::write(root.join("keep/nested/b.flac"), b"").unwrap();
+        std::fs::write(root.join("skip/c.flac"), b"").unwrap();
+        std::fs::write(root.join("skip/nested/d.flac"), b"").unwrap();
+
+        let selection = Selection::new(
+            Matcher::build(&["*.flac"]).unwrap(),
+            Matcher::empty(),
+            Matcher::any(),
+            Matcher::build(&["skip"]).unwrap(),
+        );
+
+        let mut produced = selection
+            .select_in_dir_recursive(&root)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        produced.sort();
+
+        let mut expected = vec![
+            root.join("top.flac"),
+            root.join("keep/a.flac"),
+            root.join("keep/nested/b.flac"),
+        ];
+        expected.sort();
+
+        assert_eq!(expected, produced);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn with_ignore_files() {
+        let root = std::env::temp_dir().join("akashic_test_with_ignore_files");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("cache")).unwrap();
+
+        std::fs::write(root.join("top.flac"), b"").unwrap();
+        std::fs::write(root.join("cache/debug.log"), b"").unwrap();
+        std::fs::write(root.join("cache/keep.log"), b"").unwrap();
+        std::fs::write(root.join("cache/.akashicignore"), "*.log\n!keep.log\n").unwrap();
+
+        let selection = Selection::default()
+            .with_ignore_files(&root, ".akashicignore")
+            .unwrap();
+
+        assert_eq!(selection.is_file_pattern_match(&root.join("top.flac")), true);
+        assert_eq!(
+            selection.is_file_pattern_match(&root.join("cache/debug.log")),
+            false,
+        );
+        assert_eq!(
+            selection.is_file_pattern_match(&root.join("cache/keep.log")),
+            true,
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
     #[test]
     fn select_in_dir() {
         let temp_dir = TestUtil::create_simple_dir("select_in_dir", SAMPLE_FILE_NAMES);
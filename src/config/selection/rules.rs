@@ -0,0 +1,136 @@
+//! Ordered, gitignore-style pattern matching with whitelist (negation) support.
+//!
+//! Unlike `Matcher`, which answers whether a path matches its patterns, a
+//! `RuleSet` answers whether a path is selected: it evaluates patterns in
+//! order, lets a later pattern override an earlier one, and includes a path
+//! that no pattern matched, mirroring the semantics of a `.gitignore` file.
+
+use std::path::Path;
+
+use globset::GlobBuilder;
+use globset::GlobMatcher;
+
+use crate::config::selection::MatcherError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Polarity {
+    /// A leading `!` makes a pattern a whitelist (un-ignore) rule.
+    Whitelist,
+    Ignore,
+}
+
+#[derive(Debug)]
+struct Rule {
+    polarity: Polarity,
+    /// A leading `/` anchors the pattern to the selection root, so it is
+    /// matched against the whole path instead of just the file name.
+    anchored: bool,
+    matcher: GlobMatcher,
+}
+
+/// An ordered list of include/exclude glob patterns, where the last pattern
+/// that matches a given path decides whether that path is selected.
+#[derive(Debug)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Builds a rule set out of an iterable of pattern strings. A pattern
+    /// prefixed with `!` is a whitelist rule, and a pattern prefixed with `/`
+    /// (after any `!`) is anchored to the selection root.
+    pub fn build<II, S>(pattern_strs: II) -> Result<Self, MatcherError>
+    where
+        II: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut rules = Vec::new();
+
+        for pattern_str in pattern_strs.into_iter() {
+            let raw = pattern_str.as_ref();
+
+            let (polarity, rest) = match raw.strip_prefix('!') {
+                Some(rest) => (Polarity::Whitelist, rest),
+                None => (Polarity::Ignore, raw),
+            };
+
+            let (anchored, rest) = match rest.strip_prefix('/') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+
+            // An anchored pattern is matched against the whole path, so keep
+            // `*` and `?` from crossing `/` there; otherwise `/*.log` would
+            // also match `sub/a.log`.
+            let glob = GlobBuilder::new(rest)
+                .literal_separator(anchored)
+                .build()
+                .map_err(MatcherError::InvalidPattern)?;
+
+            rules.push(Rule {
+                polarity,
+                anchored,
+                matcher: glob.compile_matcher(),
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Returns true if `path` is selected according to this rule set: the
+    /// last rule that matches wins, and a path with no matching rule is
+    /// included by default.
+    pub fn is_selected(&self, path: &Path) -> bool {
+        self.verdict_for(path).unwrap_or(true)
+    }
+
+    /// Like `is_selected`, but returns `None` instead of defaulting to
+    /// `true` when no rule in the set matched `path`. This lets a caller
+    /// that stacks several rule sets fall back to a less specific one
+    /// instead of assuming inclusion.
+    pub fn verdict_for(&self, path: &Path) -> Option<bool> {
+        let file_name = path.file_name().map(Path::new);
+        let mut verdict = None;
+
+        for rule in &self.rules {
+            let is_match = if rule.anchored {
+                rule.matcher.is_match(path)
+            } else {
+                file_name.map(|f| rule.matcher.is_match(f)).unwrap_or(false)
+            };
+
+            if is_match {
+                verdict = Some(rule.polarity == Polarity::Whitelist);
+            }
+        }
+
+        verdict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_match_wins() {
+        let rules = RuleSet::build(&["*.log", "!keep.log"]).unwrap();
+
+        assert_eq!(rules.is_selected(Path::new("cache/debug.log")), false);
+        assert_eq!(rules.is_selected(Path::new("cache/keep.log")), true);
+        assert_eq!(rules.is_selected(Path::new("cache/other.yml")), true);
+    }
+
+    #[test]
+    fn test_anchoring() {
+        let rules = RuleSet::build(&["/item.yml"]).unwrap();
+
+        assert_eq!(rules.is_selected(Path::new("item.yml")), false);
+        assert_eq!(rules.is_selected(Path::new("sub/item.yml")), true);
+
+        let rules = RuleSet::build(&["item.yml"]).unwrap();
+
+        assert_eq!(rules.is_selected(Path::new("item.yml")), false);
+        assert_eq!(rules.is_selected(Path::new("sub/item.yml")), false);
+    }
+}
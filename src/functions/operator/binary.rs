@@ -8,6 +8,8 @@ pub use self::predicate::Predicate;
 pub use self::iter_consumer::IterConsumer;
 pub use self::iter_adaptor::IterAdaptor;
 
+use std::convert::TryFrom;
+
 use crate::metadata::types::MetaVal;
 use crate::functions::Error;
 use crate::functions::util::value_producer::ValueProducer;
@@ -23,8 +25,13 @@ use crate::functions::util::value_producer::SkipWhile;
 use crate::functions::util::value_producer::TakeWhile;
 use crate::functions::util::value_producer::Intersperse;
 use crate::functions::util::value_producer::Interleave;
+use crate::functions::util::value_producer::Flatten;
+use crate::functions::util::value_producer::Chunks;
+use crate::functions::util::value_producer::Windows;
 use crate::functions::util::UnaryPred;
 use crate::functions::util::UnaryConv;
+use crate::functions::util::BinaryConv;
+use crate::functions::util::NumberLike;
 
 #[derive(Clone, Copy)]
 enum AllAny { All, Any, }
@@ -222,6 +229,173 @@ impl Impl {
             Ok(seq) => seq,
         }
     }
+
+    pub fn fold<'a, VP: ValueProducer<'a>>(vp: VP, init: MetaVal, b_conv: BinaryConv) -> Result<MetaVal, Error> {
+        let mut acc = init;
+        for res_mv in vp {
+            let mv = res_mv?;
+            acc = b_conv(acc, &mv)?;
+        }
+
+        Ok(acc)
+    }
+
+    pub fn fold_s(seq: Vec<MetaVal>, init: MetaVal, b_conv: BinaryConv) -> Result<MetaVal, Error> {
+        Self::fold(Fixed::new(seq), init, b_conv)
+    }
+
+    pub fn count<'a, VP: ValueProducer<'a>>(vp: VP) -> Result<MetaVal, Error> {
+        let mut n = 0i64;
+        for res_mv in vp {
+            res_mv?;
+            n += 1;
+        }
+
+        Ok(MetaVal::Int(n))
+    }
+
+    pub fn count_s(seq: Vec<MetaVal>) -> Result<MetaVal, Error> {
+        Ok(MetaVal::Int(seq.len() as i64))
+    }
+
+    fn sum_product<'a, VP: ValueProducer<'a>>(vp: VP, identity: i64, flag: AllAny) -> Result<MetaVal, Error> {
+        let mut acc = NumberLike::Integer(identity);
+        for res_mv in vp {
+            let mv = res_mv?;
+            let nl = NumberLike::try_from(mv)?;
+
+            match flag {
+                AllAny::All => acc += nl,
+                AllAny::Any => acc *= nl,
+            }
+        }
+
+        Ok(acc.into())
+    }
+
+    pub fn sum<'a, VP: ValueProducer<'a>>(vp: VP) -> Result<MetaVal, Error> {
+        Self::sum_product(vp, 0, AllAny::All)
+    }
+
+    pub fn sum_s(seq: Vec<MetaVal>) -> Result<MetaVal, Error> {
+        Self::sum_product(Fixed::new(seq), 0, AllAny::All)
+    }
+
+    pub fn product<'a, VP: ValueProducer<'a>>(vp: VP) -> Result<MetaVal, Error> {
+        Self::sum_product(vp, 1, AllAny::Any)
+    }
+
+    pub fn product_s(seq: Vec<MetaVal>) -> Result<MetaVal, Error> {
+        Self::sum_product(Fixed::new(seq), 1, AllAny::Any)
+    }
+
+    fn min_max<'a, VP: ValueProducer<'a>>(vp: VP, flag: AllAny) -> Result<MetaVal, Error> {
+        let mut curr: Option<NumberLike> = None;
+        for res_mv in vp {
+            let mv = res_mv?;
+            let nl = NumberLike::try_from(mv)?;
+
+            curr = Some(match curr {
+                None => nl,
+                Some(best) => {
+                    let keep_new = match flag {
+                        AllAny::All => nl.val_cmp(&best) == std::cmp::Ordering::Less,
+                        AllAny::Any => nl.val_cmp(&best) == std::cmp::Ordering::Greater,
+                    };
+
+                    if keep_new { nl } else { best }
+                },
+            });
+        }
+
+        curr.map(Into::into).ok_or(Error::EmptySequence)
+    }
+
+    pub fn min<'a, VP: ValueProducer<'a>>(vp: VP) -> Result<MetaVal, Error> {
+        Self::min_max(vp, AllAny::All)
+    }
+
+    pub fn min_s(seq: Vec<MetaVal>) -> Result<MetaVal, Error> {
+        Self::min_max(Fixed::new(seq), AllAny::All)
+    }
+
+    pub fn max<'a, VP: ValueProducer<'a>>(vp: VP) -> Result<MetaVal, Error> {
+        Self::min_max(vp, AllAny::Any)
+    }
+
+    pub fn max_s(seq: Vec<MetaVal>) -> Result<MetaVal, Error> {
+        Self::min_max(Fixed::new(seq), AllAny::Any)
+    }
+
+    // `b_conv` is handed the running best and the next candidate, and returns
+    // whichever of the two should be kept, so `min_by`/`max_by` are really
+    // just `fold` seeded with the first produced value instead of an
+    // explicit initial value.
+    fn min_max_by<'a, VP: ValueProducer<'a>>(vp: VP, b_conv: BinaryConv) -> Result<MetaVal, Error> {
+        let mut curr: Option<MetaVal> = None;
+        for res_mv in vp {
+            let mv = res_mv?;
+
+            curr = Some(match curr {
+                None => mv,
+                Some(best) => b_conv(best, &mv)?,
+            });
+        }
+
+        curr.ok_or(Error::EmptySequence)
+    }
+
+    pub fn min_by<'a, VP: ValueProducer<'a>>(vp: VP, b_conv: BinaryConv) -> Result<MetaVal, Error> {
+        Self::min_max_by(vp, b_conv)
+    }
+
+    pub fn min_by_s(seq: Vec<MetaVal>, b_conv: BinaryConv) -> Result<MetaVal, Error> {
+        Self::min_max_by(Fixed::new(seq), b_conv)
+    }
+
+    pub fn max_by<'a, VP: ValueProducer<'a>>(vp: VP, b_conv: BinaryConv) -> Result<MetaVal, Error> {
+        Self::min_max_by(vp, b_conv)
+    }
+
+    pub fn max_by_s(seq: Vec<MetaVal>, b_conv: BinaryConv) -> Result<MetaVal, Error> {
+        Self::min_max_by(Fixed::new(seq), b_conv)
+    }
+
+    pub fn flatten<'a, VP: ValueProducer<'a>>(vp: VP) -> Flatten<'a, VP> {
+        Flatten::new(vp)
+    }
+
+    pub fn flatten_s(seq: Vec<MetaVal>) -> Result<Vec<MetaVal>, Error> {
+        // Flattening on its own cannot fail.
+        match Flatten::new(Fixed::new(seq)).collect::<Result<Vec<MetaVal>, _>>() {
+            Err(_) => unreachable!(),
+            Ok(seq) => Ok(seq),
+        }
+    }
+
+    pub fn flat_map<'a, VP: ValueProducer<'a>>(vp: VP, u_conv: UnaryConv) -> Flatten<'a, Map<VP>> {
+        Flatten::new(Map::new(vp, u_conv))
+    }
+
+    pub fn flat_map_s(seq: Vec<MetaVal>, u_conv: UnaryConv) -> Result<Vec<MetaVal>, Error> {
+        Flatten::new(Map::new(Fixed::new(seq), u_conv)).collect()
+    }
+
+    pub fn chunks<'a, VP: ValueProducer<'a>>(vp: VP, n: usize) -> Result<Chunks<VP>, Error> {
+        Chunks::new(vp, n)
+    }
+
+    pub fn chunks_s(seq: Vec<MetaVal>, n: usize) -> Result<Vec<MetaVal>, Error> {
+        Chunks::new(Fixed::new(seq), n)?.collect()
+    }
+
+    pub fn windows<'a, VP: ValueProducer<'a>>(vp: VP, n: usize) -> Result<Windows<'a, VP>, Error> {
+        Windows::new(vp, n)
+    }
+
+    pub fn windows_s(seq: Vec<MetaVal>, n: usize) -> Result<Vec<MetaVal>, Error> {
+        Windows::new(Fixed::new(seq), n)?.collect()
+    }
 }
 
 #[cfg(test)]
@@ -509,4 +683,196 @@ mod tests {
             assert_eq!(expected, produced);
         }
     }
+
+    fn add_mvs(acc: MetaVal, mv: &MetaVal) -> Result<MetaVal, Error> {
+        match (acc, mv) {
+            (MetaVal::Int(l), MetaVal::Int(r)) => Ok(MetaVal::Int(l + r)),
+            _ => Err(Error::NotNumeric),
+        }
+    }
+
+    fn min_mvs(acc: MetaVal, mv: &MetaVal) -> Result<MetaVal, Error> {
+        match (&acc, mv) {
+            (MetaVal::Int(l), MetaVal::Int(r)) => if r < l { Ok(mv.clone()) } else { Ok(acc) },
+            _ => Err(Error::NotNumeric),
+        }
+    }
+
+    #[test]
+    fn test_count() {
+        let inputs_and_expected = vec![
+            (vec![], Ok(0i64)),
+            (TU::core_nested_sequence().into_iter().map(Result::Ok).collect(), Ok(TU::core_nested_sequence().len() as i64)),
+            (vec![Ok(MetaVal::Bul(true)), Err(Error::Sentinel)], Err(ErrorKind::Sentinel)),
+        ];
+
+        for (input, expected) in inputs_and_expected {
+            let produced = Impl::count(Raw::new(input)).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected.map(MetaVal::Int).map_err(Into::<ErrorKind>::into), produced);
+        }
+    }
+
+    #[test]
+    fn test_sum_s() {
+        let inputs_and_expected = vec![
+            (vec![], Ok(MetaVal::Int(0))),
+            (vec![TU::i(1), TU::i(2), TU::i(3)], Ok(MetaVal::Int(6))),
+            (vec![MetaVal::Bul(true)], Err(ErrorKind::NotNumeric)),
+        ];
+
+        for (input, expected) in inputs_and_expected {
+            let produced = Impl::sum_s(input).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
+
+    #[test]
+    fn test_product_s() {
+        let inputs_and_expected = vec![
+            (vec![], Ok(MetaVal::Int(1))),
+            (vec![TU::i(2), TU::i(3), TU::i(4)], Ok(MetaVal::Int(24))),
+            (vec![MetaVal::Bul(true)], Err(ErrorKind::NotNumeric)),
+        ];
+
+        for (input, expected) in inputs_and_expected {
+            let produced = Impl::product_s(input).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
+
+    #[test]
+    fn test_min_s() {
+        let inputs_and_expected = vec![
+            (vec![], Err(ErrorKind::EmptySequence)),
+            (vec![TU::i(3), TU::i(1), TU::i(2)], Ok(MetaVal::Int(1))),
+            (vec![MetaVal::Bul(true)], Err(ErrorKind::NotNumeric)),
+        ];
+
+        for (input, expected) in inputs_and_expected {
+            let produced = Impl::min_s(input).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
+
+    #[test]
+    fn test_max_s() {
+        let inputs_and_expected = vec![
+            (vec![], Err(ErrorKind::EmptySequence)),
+            (vec![TU::i(3), TU::i(1), TU::i(2)], Ok(MetaVal::Int(3))),
+            (vec![MetaVal::Bul(true)], Err(ErrorKind::NotNumeric)),
+        ];
+
+        for (input, expected) in inputs_and_expected {
+            let produced = Impl::max_s(input).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
+
+    #[test]
+    fn test_fold_s() {
+        let inputs_and_expected = vec![
+            ((vec![], MetaVal::Int(0)), Ok(MetaVal::Int(0))),
+            ((vec![TU::i(1), TU::i(2), TU::i(3)], MetaVal::Int(10)), Ok(MetaVal::Int(16))),
+        ];
+
+        for (inputs, expected) in inputs_and_expected {
+            let (seq, init) = inputs;
+            let produced = Impl::fold_s(seq, init, add_mvs).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
+
+    #[test]
+    fn test_min_by_s() {
+        let inputs_and_expected = vec![
+            (vec![], Err(ErrorKind::EmptySequence)),
+            (vec![TU::i(3), TU::i(1), TU::i(2)], Ok(MetaVal::Int(1))),
+        ];
+
+        for (input, expected) in inputs_and_expected {
+            let produced = Impl::min_by_s(input, min_mvs).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
+
+    fn dupe(mv: MetaVal) -> Result<MetaVal, Error> {
+        Ok(MetaVal::Seq(vec![mv.clone(), mv]))
+    }
+
+    #[test]
+    fn test_flatten_s() {
+        let inputs_and_expected = vec![
+            (vec![], vec![]),
+            (
+                vec![MetaVal::Bul(true), MetaVal::Bul(false)],
+                vec![MetaVal::Bul(true), MetaVal::Bul(false)],
+            ),
+            (
+                vec![MetaVal::Seq(vec![TU::i(1), TU::i(2)]), TU::i(3), MetaVal::Seq(vec![])],
+                vec![TU::i(1), TU::i(2), TU::i(3)],
+            ),
+        ];
+
+        for (input, expected) in inputs_and_expected {
+            let produced = Impl::flatten_s(input).unwrap();
+            assert_eq!(expected, produced);
+        }
+    }
+
+    #[test]
+    fn test_flat_map_s() {
+        let inputs_and_expected = vec![
+            (vec![], Ok(vec![])),
+            (
+                vec![TU::i(1), TU::i(2)],
+                Ok(vec![TU::i(1), TU::i(1), TU::i(2), TU::i(2)]),
+            ),
+        ];
+
+        for (input, expected) in inputs_and_expected {
+            let produced = Impl::flat_map_s(input, dupe).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
+
+    #[test]
+    fn test_chunks_s() {
+        let inputs_and_expected = vec![
+            ((vec![], 2usize), Ok(vec![])),
+            ((vec![TU::i(0)], 0), Err(ErrorKind::InvalidChunkSize)),
+            (
+                (vec![TU::i(0), TU::i(1), TU::i(2)], 2),
+                Ok(vec![MetaVal::Seq(vec![TU::i(0), TU::i(1)]), MetaVal::Seq(vec![TU::i(2)])]),
+            ),
+            (
+                (vec![TU::i(0), TU::i(1), TU::i(2), TU::i(3)], 2),
+                Ok(vec![MetaVal::Seq(vec![TU::i(0), TU::i(1)]), MetaVal::Seq(vec![TU::i(2), TU::i(3)])]),
+            ),
+        ];
+
+        for (inputs, expected) in inputs_and_expected {
+            let (seq, n) = inputs;
+            let produced = Impl::chunks_s(seq, n).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
+
+    #[test]
+    fn test_windows_s() {
+        let inputs_and_expected = vec![
+            ((vec![], 2usize), Ok(vec![])),
+            ((vec![TU::i(0)], 0), Err(ErrorKind::InvalidChunkSize)),
+            ((vec![TU::i(0)], 2), Ok(vec![])),
+            (
+                (vec![TU::i(0), TU::i(1), TU::i(2)], 2),
+                Ok(vec![MetaVal::Seq(vec![TU::i(0), TU::i(1)]), MetaVal::Seq(vec![TU::i(1), TU::i(2)])]),
+            ),
+        ];
+
+        for (inputs, expected) in inputs_and_expected {
+            let (seq, n) = inputs;
+            let produced = Impl::windows_s(seq, n).map_err(Into::<ErrorKind>::into);
+            assert_eq!(expected, produced);
+        }
+    }
 }
@@ -1,6 +1,16 @@
 pub mod number_like;
 pub mod value_producer;
+pub mod async_value_producer;
 
 pub use self::number_like::NumberLike;
 pub use self::value_producer::ValueProducer;
 pub use self::value_producer::*;
+pub use self::async_value_producer::AsyncValueProducer;
+
+use crate::metadata::types::MetaVal;
+use crate::functions::Error;
+
+/// A binary combinator over the consumer stack, used by `fold` and the
+/// `_by` comparison consumers to combine an accumulator with the next
+/// produced value.
+pub type BinaryConv = fn(MetaVal, &MetaVal) -> Result<MetaVal, Error>;
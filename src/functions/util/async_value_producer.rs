@@ -0,0 +1,400 @@
+//! Asynchronous sibling of `value_producer`.
+//!
+//! `value_producer` blocks the calling thread on every pull, which is fine
+//! when a producer is backed by values already sitting in memory, but
+//! serializes disk reads when it is ultimately fed by metadata files on
+//! disk. This module mirrors that one the way a client library's blocking
+//! `SyncClient` is mirrored by a non-blocking `AsyncClient` over the same
+//! operations: `AsyncValueProducer` is polled for its next item instead of
+//! being iterated, so an executor can make progress on other work (such as
+//! another producer's read) while one producer is waiting on I/O.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use std::task::RawWaker;
+use std::task::RawWakerVTable;
+use std::task::Waker;
+use std::thread::Thread;
+
+use crate::metadata::types::MetaVal;
+use crate::functions::Error;
+use crate::functions::util::UnaryPred;
+use crate::functions::util::UnaryConv;
+use crate::functions::util::BinaryConv;
+
+/// The asynchronous counterpart of `ValueProducer`: instead of blocking in
+/// `Iterator::next`, a caller polls for the next item and the producer
+/// returns `Poll::Pending` (registering the waker in `cx`) when it has
+/// nothing ready yet, instead of parking the thread.
+pub trait AsyncValueProducer<'a> {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<MetaVal<'a>, Error>>>;
+
+    /// Returns a future resolving to the next produced item, for use with
+    /// `.await` or `block_on`.
+    fn next(&mut self) -> Next<'_, Self>
+    where
+        Self: Unpin,
+    {
+        Next(self)
+    }
+}
+
+/// The future returned by `AsyncValueProducer::next`.
+pub struct Next<'p, VP: ?Sized>(&'p mut VP);
+
+impl<'p, 'a, VP> Future for Next<'p, VP>
+where
+    VP: AsyncValueProducer<'a> + Unpin + ?Sized,
+{
+    type Output = Option<Result<MetaVal<'a>, Error>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Pin::new(&mut *self.0).poll_next(cx)
+    }
+}
+
+pub struct AsyncFilter<VP>(VP, UnaryPred);
+
+impl<VP> AsyncFilter<VP> {
+    pub fn new(vp: VP, u_pred: UnaryPred) -> Self {
+        Self(vp, u_pred)
+    }
+}
+
+impl<'a, VP> AsyncValueProducer<'a> for AsyncFilter<VP>
+where
+    VP: AsyncValueProducer<'a> + Unpin,
+{
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<MetaVal<'a>, Error>>> {
+        loop {
+            let res_mv = match Pin::new(&mut self.0).poll_next(cx) {
+                Poll::Ready(Some(res_mv)) => res_mv,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match res_mv {
+                Err(err) => return Poll::Ready(Some(Err(err))),
+                Ok(mv) => {
+                    match (self.1)(&mv) {
+                        Ok(true) => return Poll::Ready(Some(Ok(mv))),
+                        Ok(false) => continue,
+                        Err(err) => return Poll::Ready(Some(Err(err))),
+                    }
+                },
+            }
+        }
+    }
+}
+
+pub struct AsyncMap<VP>(VP, UnaryConv);
+
+impl<VP> AsyncMap<VP> {
+    pub fn new(vp: VP, u_conv: UnaryConv) -> Self {
+        Self(vp, u_conv)
+    }
+}
+
+impl<'a, VP> AsyncValueProducer<'a> for AsyncMap<VP>
+where
+    VP: AsyncValueProducer<'a> + Unpin,
+{
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<MetaVal<'a>, Error>>> {
+        match Pin::new(&mut self.0).poll_next(cx) {
+            Poll::Ready(Some(Ok(mv))) => Poll::Ready(Some((self.1)(mv))),
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+pub struct AsyncSkip<VP>(VP, usize);
+
+impl<VP> AsyncSkip<VP> {
+    pub fn new(vp: VP, n: usize) -> Self {
+        Self(vp, n)
+    }
+}
+
+impl<'a, VP> AsyncValueProducer<'a> for AsyncSkip<VP>
+where
+    VP: AsyncValueProducer<'a> + Unpin,
+{
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<MetaVal<'a>, Error>>> {
+        while self.1 > 0 {
+            match Pin::new(&mut self.0).poll_next(cx) {
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(Some(Ok(_))) => { self.1 -= 1; },
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+pub struct AsyncTake<VP>(VP, usize);
+
+impl<VP> AsyncTake<VP> {
+    pub fn new(vp: VP, n: usize) -> Self {
+        Self(vp, n)
+    }
+}
+
+impl<'a, VP> AsyncValueProducer<'a> for AsyncTake<VP>
+where
+    VP: AsyncValueProducer<'a> + Unpin,
+{
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<MetaVal<'a>, Error>>> {
+        if self.1 == 0 {
+            return Poll::Ready(None);
+        }
+
+        match Pin::new(&mut self.0).poll_next(cx) {
+            Poll::Ready(item) => {
+                self.1 -= 1;
+                Poll::Ready(item)
+            },
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Unlike the synchronous `Chain`, this eagerly starts polling the second
+/// producer from the chain's first poll onward, so that its I/O (e.g. an
+/// open file read) can overlap with the first producer's instead of only
+/// starting once the first is exhausted. A prefetched item is held onto
+/// until the chain actually switches over to the second producer, so that
+/// nothing it produces early is lost.
+pub struct AsyncChain<'a, VPA, VPB> {
+    vp_a: VPA,
+    vp_b: VPB,
+    on_a: bool,
+    prefetched_b: Option<Option<Result<MetaVal<'a>, Error>>>,
+}
+
+impl<'a, VPA, VPB> AsyncChain<'a, VPA, VPB> {
+    pub fn new(vp_a: VPA, vp_b: VPB) -> Self {
+        Self { vp_a, vp_b, on_a: true, prefetched_b: None }
+    }
+}
+
+impl<'a, VPA, VPB> AsyncValueProducer<'a> for AsyncChain<'a, VPA, VPB>
+where
+    VPA: AsyncValueProducer<'a> + Unpin,
+    VPB: AsyncValueProducer<'a> + Unpin,
+{
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<MetaVal<'a>, Error>>> {
+        if self.on_a {
+            // Opportunistically nudge the second producer forward while the
+            // first is still being drained, so its I/O has a head start; the
+            // result (if any) is cached for when the chain switches over.
+            if self.prefetched_b.is_none() {
+                if let Poll::Ready(item) = Pin::new(&mut self.vp_b).poll_next(cx) {
+                    self.prefetched_b = Some(item);
+                }
+            }
+
+            match Pin::new(&mut self.vp_a).poll_next(cx) {
+                Poll::Ready(None) => {
+                    self.on_a = false;
+                    self.poll_next(cx)
+                },
+                other => other,
+            }
+        }
+        else {
+            match self.prefetched_b.take() {
+                Some(item) => Poll::Ready(item),
+                None => Pin::new(&mut self.vp_b).poll_next(cx),
+            }
+        }
+    }
+}
+
+/// A ready side's item is held until the other side also becomes ready
+/// (mirroring `AsyncChain`'s `prefetched_b`), so a side that resolves on one
+/// poll and a partner that's still `Pending` doesn't get silently dropped
+/// when the next poll advances that producer again.
+pub struct AsyncZip<'a, VPA, VPB> {
+    vp_a: VPA,
+    vp_b: VPB,
+    prefetched_a: Option<Option<Result<MetaVal<'a>, Error>>>,
+    prefetched_b: Option<Option<Result<MetaVal<'a>, Error>>>,
+}
+
+impl<'a, VPA, VPB> AsyncZip<'a, VPA, VPB> {
+    pub fn new(vp_a: VPA, vp_b: VPB) -> Self {
+        Self { vp_a, vp_b, prefetched_a: None, prefetched_b: None }
+    }
+}
+
+impl<'a, VPA, VPB> AsyncValueProducer<'a> for AsyncZip<'a, VPA, VPB>
+where
+    VPA: AsyncValueProducer<'a> + Unpin,
+    VPB: AsyncValueProducer<'a> + Unpin,
+{
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<MetaVal<'a>, Error>>> {
+        // Poll both sides so their I/O overlaps, rather than waiting on `a`
+        // to resolve before even starting `b`; a side that's already ready
+        // from a previous poll is left alone instead of being re-polled.
+        if self.prefetched_a.is_none() {
+            if let Poll::Ready(item) = Pin::new(&mut self.vp_a).poll_next(cx) {
+                self.prefetched_a = Some(item);
+            }
+        }
+
+        if self.prefetched_b.is_none() {
+            if let Poll::Ready(item) = Pin::new(&mut self.vp_b).poll_next(cx) {
+                self.prefetched_b = Some(item);
+            }
+        }
+
+        if self.prefetched_a.is_none() || self.prefetched_b.is_none() {
+            return Poll::Pending;
+        }
+
+        let res_a = self.prefetched_a.take().unwrap();
+        let res_b = self.prefetched_b.take().unwrap();
+
+        match (res_a, res_b) {
+            (None, _) | (_, None) => Poll::Ready(None),
+            (Some(Err(err)), _) | (_, Some(Err(err))) => Poll::Ready(Some(Err(err))),
+            (Some(Ok(a)), Some(Ok(b))) => Poll::Ready(Some(Ok(MetaVal::Seq(vec![a, b])))),
+        }
+    }
+}
+
+async fn async_next<'a, VP: AsyncValueProducer<'a> + Unpin>(vp: &mut VP) -> Option<Result<MetaVal<'a>, Error>> {
+    vp.next().await
+}
+
+/// Namespace for the async counterparts of the synchronous `Impl` consumers.
+pub struct AsyncImpl;
+
+impl AsyncImpl {
+    pub async fn nth<'a, VP: AsyncValueProducer<'a> + Unpin>(mut vp: VP, n: usize) -> Result<MetaVal<'a>, Error> {
+        let mut i = 0;
+        while let Some(res_mv) = async_next(&mut vp).await {
+            let mv = res_mv?;
+            if i == n { return Ok(mv) }
+            i += 1;
+        }
+
+        Err(Error::OutOfBounds)
+    }
+
+    pub async fn all<'a, VP: AsyncValueProducer<'a> + Unpin>(mut vp: VP, u_pred: UnaryPred) -> Result<bool, Error> {
+        while let Some(res_mv) = async_next(&mut vp).await {
+            let mv = res_mv?;
+            if !u_pred(&mv)? { return Ok(false) }
+        }
+
+        Ok(true)
+    }
+
+    pub async fn any<'a, VP: AsyncValueProducer<'a> + Unpin>(mut vp: VP, u_pred: UnaryPred) -> Result<bool, Error> {
+        while let Some(res_mv) = async_next(&mut vp).await {
+            let mv = res_mv?;
+            if u_pred(&mv)? { return Ok(true) }
+        }
+
+        Ok(false)
+    }
+
+    pub async fn find<'a, VP: AsyncValueProducer<'a> + Unpin>(mut vp: VP, u_pred: UnaryPred) -> Result<MetaVal<'a>, Error> {
+        while let Some(res_mv) = async_next(&mut vp).await {
+            let mv = res_mv?;
+            if u_pred(&mv)? { return Ok(mv) }
+        }
+
+        Err(Error::ItemNotFound)
+    }
+
+    pub async fn fold<'a, VP: AsyncValueProducer<'a> + Unpin>(mut vp: VP, init: MetaVal<'a>, b_conv: BinaryConv) -> Result<MetaVal<'a>, Error> {
+        let mut acc = init;
+        while let Some(res_mv) = async_next(&mut vp).await {
+            let mv = res_mv?;
+            acc = b_conv(acc, &mv)?;
+        }
+
+        Ok(acc)
+    }
+}
+
+/// Adapts an `AsyncValueProducer` into a plain (synchronous) `ValueProducer`
+/// by blocking on `poll_next` for each pulled item. This lets the existing
+/// `Impl` consumers (`nth`, `fold`, `sum`, ...) drive an async source
+/// without each of them needing an async-aware duplicate.
+pub struct Blocking<VP>(VP);
+
+impl<VP> Blocking<VP> {
+    pub fn new(vp: VP) -> Self {
+        Self(vp)
+    }
+}
+
+impl<'a, VP> Iterator for Blocking<VP>
+where
+    VP: AsyncValueProducer<'a> + Unpin,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(AsyncValueProducer::next(&mut self.0))
+    }
+}
+
+/// Drives a future to completion on the current thread, parking it between
+/// polls instead of busy-waiting. This is enough to let the synchronous
+/// `Impl` consumers drive an `AsyncValueProducer`-based source without
+/// pulling in a full executor.
+pub fn block_on<F: Future>(mut fut: F) -> F::Output {
+    let waker = thread_waker(std::thread::current());
+    let mut cx = Context::from_waker(&waker);
+
+    // Safety: `fut` is a local that is never moved for the rest of this
+    // function, so pinning it on the stack is sound.
+    let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(val) => return val,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+fn thread_waker(thread: Thread) -> Waker {
+    fn clone(data: *const ()) -> RawWaker {
+        let arc = unsafe { Arc::from_raw(data as *const Thread) };
+        let cloned = arc.clone();
+        std::mem::forget(arc);
+        RawWaker::new(Arc::into_raw(cloned) as *const (), &VTABLE)
+    }
+
+    fn wake(data: *const ()) {
+        let arc = unsafe { Arc::from_raw(data as *const Thread) };
+        arc.unpark();
+    }
+
+    fn wake_by_ref(data: *const ()) {
+        let arc = unsafe { Arc::from_raw(data as *const Thread) };
+        arc.unpark();
+        std::mem::forget(arc);
+    }
+
+    fn drop_waker(data: *const ()) {
+        unsafe { Arc::from_raw(data as *const Thread) };
+    }
+
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop_waker);
+
+    let raw = RawWaker::new(Arc::into_raw(Arc::new(thread)) as *const (), &VTABLE);
+    unsafe { Waker::from_raw(raw) }
+}
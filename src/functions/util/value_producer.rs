@@ -0,0 +1,488 @@
+//! Lazy adaptors over the `MetaVal` consumer stack.
+//!
+//! A `ValueProducer` is anything that can be pulled from to yield a
+//! `Result<MetaVal, Error>`, so that an error discovered partway through a
+//! stream (e.g. a malformed metadata file) can be propagated instead of
+//! panicking. The adaptors below compose to build up the stack of producers
+//! that `functions::operator::binary::Impl` is driven by.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use crate::metadata::types::MetaVal;
+use crate::functions::Error;
+use crate::functions::util::UnaryPred;
+use crate::functions::util::UnaryConv;
+
+/// Anything that yields `MetaVal`s (or an error) is a `ValueProducer`; this
+/// is a marker trait, blanket-implemented for any matching iterator, so that
+/// producers and adaptors can be used interchangeably wherever one is
+/// expected.
+pub trait ValueProducer<'a>: Iterator<Item = Result<MetaVal<'a>, Error>> {}
+
+impl<'a, T> ValueProducer<'a> for T
+where
+    T: Iterator<Item = Result<MetaVal<'a>, Error>>,
+{}
+
+/// Wraps an already-materialized sequence of `MetaVal`s as a producer.
+pub struct Fixed<'a>(std::vec::IntoIter<MetaVal<'a>>);
+
+impl<'a> Fixed<'a> {
+    pub fn new(seq: Vec<MetaVal<'a>>) -> Self {
+        Self(seq.into_iter())
+    }
+}
+
+impl<'a> Iterator for Fixed<'a> {
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Ok)
+    }
+}
+
+/// Wraps an already-materialized sequence of fallible `MetaVal`s as a
+/// producer. Used mainly in tests, to inject an error partway through a
+/// stream.
+pub struct Raw<'a>(std::vec::IntoIter<Result<MetaVal<'a>, Error>>);
+
+impl<'a> Raw<'a> {
+    pub fn new(seq: Vec<Result<MetaVal<'a>, Error>>) -> Self {
+        Self(seq.into_iter())
+    }
+}
+
+impl<'a> Iterator for Raw<'a> {
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+pub struct Filter<VP>(VP, UnaryPred);
+
+impl<VP> Filter<VP> {
+    pub fn new(vp: VP, u_pred: UnaryPred) -> Self {
+        Self(vp, u_pred)
+    }
+}
+
+impl<'a, VP> Iterator for Filter<VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mv = match self.0.next()? {
+                Ok(mv) => mv,
+                Err(err) => return Some(Err(err)),
+            };
+
+            match (self.1)(&mv) {
+                Ok(true) => return Some(Ok(mv)),
+                Ok(false) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+pub struct Map<VP>(VP, UnaryConv);
+
+impl<VP> Map<VP> {
+    pub fn new(vp: VP, u_conv: UnaryConv) -> Self {
+        Self(vp, u_conv)
+    }
+}
+
+impl<'a, VP> Iterator for Map<VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0.next()? {
+            Ok(mv) => Some((self.1)(mv)),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+pub struct StepBy<VP>(VP, usize, usize);
+
+impl<VP> StepBy<VP> {
+    pub fn new(vp: VP, step: usize) -> Result<Self, Error> {
+        if step == 0 {
+            return Err(Error::InvalidStep);
+        }
+
+        Ok(Self(vp, step, 0))
+    }
+}
+
+impl<'a, VP> Iterator for StepBy<VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let res_mv = self.0.next()?;
+            let at_step = self.2 % self.1 == 0;
+            self.2 += 1;
+
+            match res_mv {
+                Err(err) => return Some(Err(err)),
+                Ok(mv) => if at_step { return Some(Ok(mv)) } else { continue },
+            }
+        }
+    }
+}
+
+pub struct Chain<VPA, VPB>(VPA, VPB, bool);
+
+impl<VPA, VPB> Chain<VPA, VPB> {
+    pub fn new(vp_a: VPA, vp_b: VPB) -> Self {
+        Self(vp_a, vp_b, false)
+    }
+}
+
+impl<'a, VPA, VPB> Iterator for Chain<VPA, VPB>
+where
+    VPA: ValueProducer<'a>,
+    VPB: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.2 {
+            match self.0.next() {
+                None => {
+                    self.2 = true;
+                    self.next()
+                },
+                item => item,
+            }
+        }
+        else {
+            self.1.next()
+        }
+    }
+}
+
+pub struct Zip<VPA, VPB>(VPA, VPB);
+
+impl<VPA, VPB> Zip<VPA, VPB> {
+    pub fn new(vp_a: VPA, vp_b: VPB) -> Self {
+        Self(vp_a, vp_b)
+    }
+}
+
+impl<'a, VPA, VPB> Iterator for Zip<VPA, VPB>
+where
+    VPA: ValueProducer<'a>,
+    VPB: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res_a = self.0.next()?;
+        let res_b = self.1.next()?;
+
+        match (res_a, res_b) {
+            (Err(err), _) => Some(Err(err)),
+            (_, Err(err)) => Some(Err(err)),
+            (Ok(a), Ok(b)) => Some(Ok(MetaVal::Seq(vec![a, b]))),
+        }
+    }
+}
+
+pub struct Skip<'a, VP>(VP, usize, PhantomData<&'a ()>);
+
+impl<'a, VP> Skip<'a, VP> {
+    pub fn new(vp: VP, n: usize) -> Self {
+        Self(vp, n, PhantomData)
+    }
+}
+
+impl<'a, VP> Iterator for Skip<'a, VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.1 > 0 {
+            self.1 -= 1;
+            let res_mv = self.0.next()?;
+
+            // Still propagate errors encountered while skipping.
+            if let Err(err) = res_mv { return Some(Err(err)) }
+        }
+
+        self.0.next()
+    }
+}
+
+pub struct Take<'a, VP>(VP, usize, PhantomData<&'a ()>);
+
+impl<'a, VP> Take<'a, VP> {
+    pub fn new(vp: VP, n: usize) -> Self {
+        Self(vp, n, PhantomData)
+    }
+}
+
+impl<'a, VP> Iterator for Take<'a, VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.1 > 0 {
+            self.1 -= 1;
+            self.0.next()
+        }
+        else {
+            None
+        }
+    }
+}
+
+pub struct SkipWhile<VP>(VP, UnaryPred, bool);
+
+impl<VP> SkipWhile<VP> {
+    pub fn new(vp: VP, u_pred: UnaryPred) -> Self {
+        Self(vp, u_pred, true)
+    }
+}
+
+impl<'a, VP> Iterator for SkipWhile<VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.2 {
+            loop {
+                match self.0.next()? {
+                    Err(err) => return Some(Err(err)),
+                    Ok(mv) => {
+                        match (self.1)(&mv) {
+                            Ok(true) => continue,
+                            Ok(false) => {
+                                self.2 = false;
+                                return Some(Ok(mv))
+                            },
+                            Err(err) => return Some(Err(err)),
+                        }
+                    },
+                }
+            }
+        }
+
+        self.0.next()
+    }
+}
+
+pub struct TakeWhile<VP>(VP, UnaryPred, bool);
+
+impl<VP> TakeWhile<VP> {
+    pub fn new(vp: VP, u_pred: UnaryPred) -> Self {
+        Self(vp, u_pred, true)
+    }
+}
+
+impl<'a, VP> Iterator for TakeWhile<VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.2 { return None }
+
+        match self.0.next()? {
+            Ok(mv) => {
+                match (self.1)(&mv) {
+                    Ok(true) => Some(Ok(mv)),
+                    Ok(false) => {
+                        self.2 = false;
+                        None
+                    },
+                    Err(err) => Some(Err(err)),
+                }
+            },
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+pub struct Intersperse<'a, VP>(VP, MetaVal<'a>, bool);
+
+impl<'a, VP> Intersperse<'a, VP> {
+    pub fn new(vp: VP, mv: MetaVal<'a>) -> Self {
+        Self(vp, mv, false)
+    }
+}
+
+impl<'a, VP> Iterator for Intersperse<'a, VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.2 {
+            self.2 = false;
+            Some(Ok(self.1.clone()))
+        }
+        else {
+            let next = self.0.next();
+            if next.is_some() { self.2 = true; }
+            next
+        }
+    }
+}
+
+pub struct Interleave<VPA, VPB>(VPA, VPB, bool);
+
+impl<VPA, VPB> Interleave<VPA, VPB> {
+    pub fn new(vp_a: VPA, vp_b: VPB) -> Self {
+        Self(vp_a, vp_b, false)
+    }
+}
+
+impl<'a, VPA, VPB> Iterator for Interleave<VPA, VPB>
+where
+    VPA: ValueProducer<'a>,
+    VPB: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.2 = !self.2;
+
+        if self.2 { self.0.next().or_else(|| self.1.next()) }
+        else { self.1.next().or_else(|| self.0.next()) }
+    }
+}
+
+/// Lazily unwraps one level of nesting: a `MetaVal::Seq` is expanded into its
+/// elements, which are yielded one at a time before the underlying producer
+/// is pulled again. Non-sequence items pass through unchanged.
+pub struct Flatten<'a, VP>(VP, VecDeque<MetaVal<'a>>)
+where
+    VP: ValueProducer<'a>,
+;
+
+impl<'a, VP> Flatten<'a, VP>
+where
+    VP: ValueProducer<'a>,
+{
+    pub fn new(vp: VP) -> Self {
+        Self(vp, VecDeque::new())
+    }
+}
+
+impl<'a, VP> Iterator for Flatten<'a, VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Drain the holding queue before pulling the underlying producer.
+        match self.1.pop_front() {
+            Some(mv) => Some(Ok(mv)),
+            None => {
+                match self.0.next()? {
+                    Ok(MetaVal::Seq(seq)) => {
+                        self.1.extend(seq);
+                        self.next()
+                    },
+                    other => Some(other),
+                }
+            },
+        }
+    }
+}
+
+/// Groups consecutive produced items into disjoint `MetaVal::Seq` blocks of
+/// up to `n` items; the final block may be shorter if the producer runs out
+/// partway through one.
+pub struct Chunks<VP>(VP, usize);
+
+impl<VP> Chunks<VP> {
+    pub fn new(vp: VP, n: usize) -> Result<Self, Error> {
+        if n == 0 {
+            return Err(Error::InvalidChunkSize);
+        }
+
+        Ok(Self(vp, n))
+    }
+}
+
+impl<'a, VP> Iterator for Chunks<VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block = Vec::with_capacity(self.1);
+
+        for _ in 0..self.1 {
+            match self.0.next() {
+                Some(Ok(mv)) => block.push(mv),
+                Some(Err(err)) => return Some(Err(err)),
+                None => break,
+            }
+        }
+
+        if block.is_empty() { None } else { Some(Ok(MetaVal::Seq(block))) }
+    }
+}
+
+/// Yields overlapping runs of exactly `n` consecutive produced items, by
+/// keeping a sliding buffer of the last `n` values and emitting a clone of it
+/// each time it is full.
+pub struct Windows<'a, VP>(VP, usize, VecDeque<MetaVal<'a>>);
+
+impl<'a, VP> Windows<'a, VP> {
+    pub fn new(vp: VP, n: usize) -> Result<Self, Error> {
+        if n == 0 {
+            return Err(Error::InvalidChunkSize);
+        }
+
+        Ok(Self(vp, n, VecDeque::with_capacity(n)))
+    }
+}
+
+impl<'a, VP> Iterator for Windows<'a, VP>
+where
+    VP: ValueProducer<'a>,
+{
+    type Item = Result<MetaVal<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.2.len() < self.1 {
+            match self.0.next()? {
+                Ok(mv) => self.2.push_back(mv),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        let window: Vec<MetaVal<'a>> = self.2.iter().cloned().collect();
+        self.2.pop_front();
+
+        Some(Ok(MetaVal::Seq(window)))
+    }
+}
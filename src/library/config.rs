@@ -0,0 +1,237 @@
+//! Loads a `Config` from a cascading stack of layers, so a subtree can
+//! override just the keys it cares about (`selection`, `sort_order`,
+//! `meta_format`, ...) without duplicating the whole document.
+//!
+//! This imports Mercurial's config-layer design: each loaded file is a
+//! layer, an `%include <relative-path>` directive pushes another layer
+//! (resolved relative to the including file), and an `%unset <key>`
+//! directive tombstones a previously set key. Layers are merged in the
+//! order they are encountered, with later layers winning; a file's own
+//! content always wins over anything it includes, and its own `%unset`
+//! directives are applied last, after its includes and its own body have
+//! both been merged in.
+//!
+//! `Config::load` resolves one such stack into a single `Config`.
+//! `MetaProcessor::process_meta_file` and `MetaFinalizer::get_metadata_with_config`
+//! take an already-resolved `Config` and do not look up or load config files
+//! for the directory being processed themselves.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_yaml::Mapping;
+use serde_yaml::Value;
+
+use config::meta_format::MetaFormat;
+use config::selection::Selection;
+use config::sort_order::SortOrder;
+use config::fallback_method::FallbackMethod;
+use config::fallback_method::FallbackSpec;
+use metadata::location::MetaLocation;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    Yaml(PathBuf, serde_yaml::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Io(path, err) => write!(f, "unable to read config file {}: {}", path.display(), err),
+            Error::Yaml(path, err) => write!(f, "invalid config syntax in {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, err) => Some(err),
+            Error::Yaml(_, err) => Some(err),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub selection: Selection,
+    pub sort_order: SortOrder,
+    pub item_fn: String,
+    pub self_fn: String,
+    pub meta_format: MetaFormat,
+    pub fallbacks: FallbackSpec,
+    pub default_fallback: FallbackMethod,
+
+    /// How many worker threads `MetaProcessor`/`LibraryScanner` may use for
+    /// independent reads and plexing (e.g. `composite_item_file`'s per-
+    /// location reads, or one directory's siblings per thread). `1` (the
+    /// default) keeps everything on the calling thread; anything greater
+    /// opts into a rayon-backed parallel path sized to this many threads.
+    pub max_workers: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let selection = Selection::default();
+        let sort_order = SortOrder::default();
+        let meta_format = MetaFormat::default();
+        let item_fn = format!("{}.{}", MetaLocation::Siblings.default_file_name(), meta_format.default_file_extension());
+        let self_fn = format!("{}.{}", MetaLocation::Contains.default_file_name(), meta_format.default_file_extension());
+        let fallbacks = FallbackSpec::default();
+        let default_fallback = FallbackMethod::default();
+        let max_workers = 1;
+
+        Config {
+            selection,
+            sort_order,
+            item_fn,
+            self_fn,
+            meta_format,
+            fallbacks,
+            default_fallback,
+            max_workers,
+        }
+    }
+}
+
+impl Config {
+    /// Resolves a `Config` from `path`, following any `%include` layers it
+    /// pulls in and applying any `%unset` directives it contains.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let merged = ConfigLoader::load_layer(path.as_ref())?;
+
+        serde_yaml::from_value(Value::Mapping(merged))
+            .map_err(|err| Error::Yaml(path.as_ref().to_owned(), err))
+    }
+}
+
+/// Splits a layer's raw text into its `%include` targets, its `%unset`
+/// keys, and the remaining YAML body text, in the order each directive was
+/// encountered. Pure/line-based, so it can be tested without touching the
+/// filesystem.
+fn parse_layer_text(text: &str) -> (Vec<String>, Vec<String>, String) {
+    let mut includes = Vec::new();
+    let mut unsets = Vec::new();
+    let mut body_lines = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some(rest) = trimmed.strip_prefix("%include ") {
+            includes.push(rest.trim().to_owned());
+        }
+        else if let Some(rest) = trimmed.strip_prefix("%unset ") {
+            unsets.push(rest.trim().to_owned());
+        }
+        else {
+            body_lines.push(line);
+        }
+    }
+
+    (includes, unsets, body_lines.join("\n"))
+}
+
+/// Merges `body` on top of `base`, with keys in `body` overriding matching
+/// keys in `base`.
+fn merge_mapping(base: &mut Mapping, body: Mapping) {
+    for (key, value) in body {
+        base.insert(key, value);
+    }
+}
+
+struct ConfigLoader;
+
+impl ConfigLoader {
+    /// Resolves `path` and everything it `%include`s into a single merged
+    /// `Mapping`, applying its own `%unset` directives last.
+    fn load_layer(path: &Path) -> Result<Mapping, Error> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| Error::Io(path.to_owned(), err))?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let (includes, unsets, body_text) = parse_layer_text(&text);
+
+        let mut merged = Mapping::new();
+
+        for include in includes {
+            let included = Self::load_layer(&dir.join(include))?;
+            merge_mapping(&mut merged, included);
+        }
+
+        if !body_text.trim().is_empty() {
+            let body: Mapping = serde_yaml::from_str(&body_text)
+                .map_err(|err| Error::Yaml(path.to_owned(), err))?;
+            merge_mapping(&mut merged, body);
+        }
+
+        for key in unsets {
+            merged.remove(&Value::String(key));
+        }
+
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge_mapping;
+    use super::parse_layer_text;
+
+    use serde_yaml::Mapping;
+    use serde_yaml::Value;
+
+    #[test]
+    fn test_parse_layer_text() {
+        let text = "\
+%include base.yml
+sort_order: name
+%unset fallbacks
+item_fn: item_meta.yml
+";
+
+        let (includes, unsets, body) = parse_layer_text(text);
+
+        assert_eq!(includes, vec!["base.yml".to_owned()]);
+        assert_eq!(unsets, vec!["fallbacks".to_owned()]);
+
+        let body: Mapping = serde_yaml::from_str(&body).unwrap();
+        assert_eq!(body.get(&Value::String("sort_order".to_owned())), Some(&Value::String("name".to_owned())));
+        assert_eq!(body.get(&Value::String("item_fn".to_owned())), Some(&Value::String("item_meta.yml".to_owned())));
+    }
+
+    #[test]
+    fn test_merge_mapping_last_wins() {
+        let mut base: Mapping = serde_yaml::from_str("sort_order: name\nitem_fn: item.yml").unwrap();
+        let overlay: Mapping = serde_yaml::from_str("sort_order: mod_time").unwrap();
+
+        merge_mapping(&mut base, overlay);
+
+        assert_eq!(base.get(&Value::String("sort_order".to_owned())), Some(&Value::String("mod_time".to_owned())));
+        assert_eq!(base.get(&Value::String("item_fn".to_owned())), Some(&Value::String("item.yml".to_owned())));
+    }
+
+    #[test]
+    fn test_load_with_include_and_unset() {
+        let dir = std::env::temp_dir().join(format!("akashic_config_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let base_path = dir.join("base.yml");
+        std::fs::write(&base_path, "sort_order: name\nitem_fn: item.yml\nself_fn: self.yml\n").unwrap();
+
+        let leaf_path = dir.join("leaf.yml");
+        std::fs::write(
+            &leaf_path,
+            "%include base.yml\nsort_order: mod_time\n%unset self_fn\n",
+        ).unwrap();
+
+        let merged = super::ConfigLoader::load_layer(&leaf_path).unwrap();
+
+        assert_eq!(merged.get(&Value::String("sort_order".to_owned())), Some(&Value::String("mod_time".to_owned())));
+        assert_eq!(merged.get(&Value::String("item_fn".to_owned())), Some(&Value::String("item.yml".to_owned())));
+        assert_eq!(merged.get(&Value::String("self_fn".to_owned())), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,3 @@
+//! Top-level configuration for a library, on top of a cascading file format.
+
+pub mod config;
@@ -1,34 +1,18 @@
 //! This is intended to be the main public API of the library.
 
-use std::path::Path;
 use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
 
-use config::Config;
-use metadata::types::MetaBlock;
-use metadata::processor::MetaProcessor;
-use metadata::processor::Error as ProcessorError;
-use config::agg_method::AggMethod;
-
-#[derive(Debug)]
-pub enum Error {
-    CannotProcessMetadata(ProcessorError),
-}
+use failure::Error;
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match *self {
-            Error::CannotProcessMetadata(ref err) => write!(f, "cannot process metadata: {}", err),
-        }
-    }
-}
-
-impl std::error::Error for Error {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match *self {
-            Error::CannotProcessMetadata(ref err) => Some(err),
-        }
-    }
-}
+use config::agg_method::AggMethod;
+use config::sort_order::SortOrder;
+use library::config::Config;
+use metadata::location::MetaLocation;
+use metadata::processor::MetaProcessor;
+use metadata::reader::yaml::YamlMetaReader;
+use metadata::types::MetaBlock;
 
 pub struct MetaFinalizer;
 
@@ -53,19 +37,90 @@ impl MetaFinalizer {
         Self::get_metadata_with_config_and_aggs(item_path, &config, agg_methods)
     }
 
+    /// Resolves `item_path`'s own metadata (merging its `Siblings` entry
+    /// from its parent's `item_fn` with its `Contains` entry from its own
+    /// `self_fn`, if any), then, for each key in `agg_methods`, gathers that
+    /// key's values from `item_path`'s immediate children and folds them in
+    /// with the named `AggMethod`, writing the result back into the
+    /// returned block.
+    ///
+    /// Aggregation is bottom-up: each child's own metadata is resolved (and
+    /// aggregated over *its* children, recursively) before its values are
+    /// folded into the parent, so a grandparent aggregates already-
+    /// aggregated child results rather than raw leaf values. A child that
+    /// genuinely fails to resolve (not merely lacking a `self_fn`) is a real
+    /// error and is propagated rather than silently dropped from the fold.
     pub fn get_metadata_with_config_and_aggs<P: AsRef<Path>>(
         item_path: P,
         config: &Config,
         agg_methods: &BTreeMap<String, AggMethod>,
     ) -> Result<MetaBlock, Error>
     {
-        let mb = MetaProcessor::process_item_file(
-            item_path,
-            config.meta_format,
-            &config.selection,
-            config.sort_order,
-        ).map_err(Error::CannotProcessMetadata)?;
+        let item_path = item_path.as_ref();
+
+        let mut mb = MetaProcessor::<YamlMetaReader>::process_item_file(item_path, MetaLocation::Siblings, config)?;
+
+        if let Some(contains_mb) = Self::contains_if_any(item_path, config)? {
+            mb.extend(contains_mb);
+        }
+
+        if !agg_methods.is_empty() {
+            let child_blocks: Vec<MetaBlock> = Self::child_paths(item_path, config)?
+                .into_iter()
+                .map(|child_path| Self::get_metadata_with_config_and_aggs(&child_path, config, agg_methods))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for (key, method) in agg_methods {
+                let values = child_blocks.iter().filter_map(|child_mb| child_mb.get(key).cloned());
+
+                if let Some(aggregated) = method.fold(values) {
+                    mb.insert(key.to_owned(), aggregated);
+                }
+            }
+        }
 
         Ok(mb)
     }
+
+    /// Resolves `item_path`'s `Contains` entry (from its own `self_fn`), if
+    /// it has one. A directory item commonly does; a leaf file commonly
+    /// doesn't, and that absence is not an error — only the `Siblings`
+    /// entry assigned by the parent is required. A `self_fn` that *is*
+    /// present but fails to parse is still a real error and is propagated.
+    fn contains_if_any(item_path: &Path, config: &Config) -> Result<Option<MetaBlock>, Error> {
+        let meta_path = match MetaLocation::Contains.get_meta_path(item_path) {
+            Ok(meta_path) => meta_path,
+            Err(_) => return Ok(None),
+        };
+
+        if !meta_path.is_file() {
+            return Ok(None);
+        }
+
+        MetaProcessor::<YamlMetaReader>::process_item_file(item_path, MetaLocation::Contains, config).map(Some)
+    }
+
+    /// The paths of `item_path`'s immediate children, as described by its
+    /// own `item_fn`, sorted per `config.sort_order` so that `Join`
+    /// aggregation sees a deterministic, meaningful order. Returns an empty
+    /// list if `item_path` has no `item_fn` of its own (i.e. it isn't a
+    /// directory containing further items).
+    fn child_paths(item_path: &Path, config: &Config) -> Result<Vec<PathBuf>, Error> {
+        let meta_path = item_path.join(&config.item_fn);
+
+        if !meta_path.is_file() {
+            return Ok(Vec::new());
+        }
+
+        let siblings = MetaProcessor::<YamlMetaReader>::process_meta_file(&meta_path, MetaLocation::Siblings, config)?;
+        let mut paths: Vec<PathBuf> = siblings.into_iter().map(|(path, _)| path).collect();
+
+        match config.sort_order {
+            SortOrder::Name => paths.sort(),
+            SortOrder::ModTime => paths.sort_by_key(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok()),
+            _ => {},
+        }
+
+        Ok(paths)
+    }
 }
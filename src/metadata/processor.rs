@@ -2,7 +2,7 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::collections::HashMap;
 use std::marker::PhantomData;
-// use std::collections::hash_map::Entry;
+use std::time::SystemTime;
 
 use failure::ResultExt;
 use failure::Error;
@@ -14,6 +14,31 @@ use metadata::location::MetaLocation;
 use metadata::reader::MetaReader;
 use metadata::plexer::MetaPlexer;
 
+// Holds the result of plexing a single meta file, along with the meta file's
+// last-modified timestamp at the time it was read, so a later lookup can tell
+// whether the on-disk file has changed since.
+struct CachedEntry {
+    meta_blocks: HashMap<PathBuf, MetaBlock>,
+    mtime: SystemTime,
+}
+
+// A simple mtime-based cache of plexed meta files, keyed by the meta file path.
+// This avoids re-reading and re-plexing the same `item.yml`/`self.yml` once per
+// sibling item when walking a large library, mirroring the lazy/cached parse
+// strategy Mercurial uses for its dirstate: a parsed representation is kept and
+// only re-derived when the backing file's identity/mtime changes.
+pub struct MetaCache(HashMap<PathBuf, CachedEntry>);
+
+impl MetaCache {
+    pub fn new() -> Self {
+        MetaCache(HashMap::new())
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
 pub struct MetaProcessor<MR>(PhantomData<MR>);
 
 impl<MR> MetaProcessor<MR>
@@ -60,6 +85,14 @@ where
 
     // Processes multiple locations for a target item at once, merging the results.
     // Merging is "combine-last", so matching result keys for subsequent locations override earlier keys.
+    //
+    // When `config.max_workers` is greater than 1 (and the `rayon` feature is
+    // enabled), the independent per-location reads are fanned out across a
+    // thread pool sized to `max_workers`. Locations still merge in their
+    // original order regardless of which one finishes first: results are
+    // collected into an indexed `Vec`, sorted back into place, and then
+    // `extend`ed sequentially, so combine-last ordering is unaffected by
+    // parallelism.
     pub fn composite_item_file<P, II>(
         item_path: P,
         meta_locations: II,
@@ -69,10 +102,20 @@ where
         P: AsRef<Path>,
         II: IntoIterator<Item = MetaLocation>,
     {
+        let item_path = item_path.as_ref();
+        let meta_locations: Vec<MetaLocation> = meta_locations.into_iter().collect();
+
+        #[cfg(feature = "rayon")]
+        {
+            if config.max_workers > 1 {
+                return Self::composite_item_file_parallel(item_path, meta_locations, config);
+            }
+        }
+
         let mut comp_mb = MetaBlock::new();
 
-        for meta_location in meta_locations.into_iter() {
-            let mb = Self::process_item_file(&item_path, meta_location, &config)?;
+        for meta_location in meta_locations {
+            let mb = Self::process_item_file(item_path, meta_location, config)?;
 
             comp_mb.extend(mb);
         }
@@ -80,48 +123,89 @@ where
         Ok(comp_mb)
     }
 
-    // pub fn process_meta_file_cached<'c, MR, P>(
-    //     meta_path: P,
-    //     meta_location: MetaLocation,
-    //     config: &Config,
-    //     cache: &'c mut HashMap<PathBuf, HashMap<PathBuf, MetaBlock>>,
-    //     force: bool,
-    // ) -> Result<&'c HashMap<PathBuf, MetaBlock>, Error>
-    // where
-    //     MR: MetaReader,
-    //     P: AsRef<Path>,
-    // {
-    //     let meta_path = meta_path.as_ref();
-
-    //     if force {
-    //         cache.remove(meta_path);
-    //     }
-
-    //     let meta_file_results = match cache.entry(meta_path.to_owned()) {
-    //         Entry::Occupied(e) => e.into_mut(),
-    //         Entry::Vacant(e) => e.insert(Self::process_meta_file::<MR, _>(meta_path, meta_location, config)?),
-    //     };
-
-    //     Ok(meta_file_results)
-    // }
-
-    // pub fn process_item_file_cached<'c, MR, P>(
-    //     item_path: P,
-    //     meta_location: MetaLocation,
-    //     config: &Config,
-    //     cache: &'c mut HashMap<PathBuf, HashMap<PathBuf, MetaBlock>>,
-    //     force: bool,
-    // ) -> Result<&'c MetaBlock, Error>
-    // where
-    //     MR: MetaReader,
-    //     P: AsRef<Path>,
-    // {
-    //     let meta_path = meta_location.get_meta_path(&item_path)?;
-
-    //     let processed_meta_file = Self::process_meta_file_cached::<MR, _>(&meta_path, meta_location, config, cache, force)?;
-    //     processed_meta_file.get(item_path.as_ref())
-    //         .ok_or(bail!("item path not found in processed metadata: \"{}\"", item_path.as_ref().to_string_lossy()))
-    // }
+    #[cfg(feature = "rayon")]
+    fn composite_item_file_parallel(
+        item_path: &Path,
+        meta_locations: Vec<MetaLocation>,
+        config: &Config,
+    ) -> Result<MetaBlock, Error>
+    {
+        use rayon::prelude::*;
+
+        // A fresh, scoped pool sized to this call's own `config.max_workers`,
+        // matching `LibraryScanner::par_scan`'s approach. A shared global
+        // pool (sized once from whichever call happens to run first) would
+        // silently ignore `max_workers` on every later call with a
+        // different value, which matters here since callers can use
+        // different `max_workers` per directory.
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.max_workers.max(1))
+            .build()
+            .unwrap();
+
+        let mut results: Vec<(usize, Result<MetaBlock, Error>)> = pool.install(|| {
+            meta_locations.into_par_iter()
+                .enumerate()
+                .map(|(idx, meta_location)| (idx, Self::process_item_file(item_path, meta_location, config)))
+                .collect()
+        });
+
+        results.sort_by_key(|(idx, _)| *idx);
+
+        let mut comp_mb = MetaBlock::new();
+
+        for (_, result) in results {
+            comp_mb.extend(result?);
+        }
+
+        Ok(comp_mb)
+    }
+
+    pub fn process_meta_file_cached<'c, P>(
+        meta_path: P,
+        meta_location: MetaLocation,
+        config: &Config,
+        cache: &'c mut MetaCache,
+        force: bool,
+    ) -> Result<&'c HashMap<PathBuf, MetaBlock>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let meta_path = meta_path.as_ref();
+        let mtime = meta_path.metadata().context(ErrorKind::CannotAccessPath)?.modified().context(ErrorKind::CannotAccessPath)?;
+
+        // Cheaply check whether the cached entry (if any) is still valid before
+        // doing any fallible work, so the fallible re-read below never happens
+        // while a mutable borrow of the cache is held.
+        let is_valid = !force
+            && cache.0.get(meta_path).map_or(false, |entry| entry.mtime == mtime);
+
+        if !is_valid {
+            let meta_blocks = Self::process_meta_file(meta_path, meta_location, config)?;
+            cache.0.insert(meta_path.to_owned(), CachedEntry { meta_blocks, mtime });
+        }
+
+        Ok(&cache.0.get(meta_path).unwrap().meta_blocks)
+    }
+
+    pub fn process_item_file_cached<'c, P>(
+        item_path: P,
+        meta_location: MetaLocation,
+        config: &Config,
+        cache: &'c mut MetaCache,
+        force: bool,
+    ) -> Result<&'c MetaBlock, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let item_path = item_path.as_ref();
+        let meta_path = meta_location.get_meta_path(&item_path)?;
+
+        let processed_meta_file = Self::process_meta_file_cached(&meta_path, meta_location, config, cache, force)?;
+
+        processed_meta_file.get(item_path)
+            .ok_or_else(|| failure::err_msg("no metadata found"))
+    }
 }
 
 #[cfg(test)]
@@ -2,22 +2,40 @@
 //! need a stream of meta blocks from various sources.
 
 use std::borrow::Cow;
+use std::io::Error as IoError;
 use std::path::Path;
+use std::path::PathBuf;
 use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+use std::sync::mpsc::sync_channel;
+use std::thread;
 
 use config::selection::Selection;
 use config::sort_order::SortOrder;
 use config::meta_format::MetaFormat;
+use library::config::Config;
+use metadata::location::MetaLocation;
 use metadata::types::MetaBlock;
 use metadata::processor::MetaProcessor;
 use metadata::processor::Error as ProcessorError;
+use metadata::reader::yaml::YamlMetaReader;
 use util::file_walkers::FileWalker;
 use util::file_walkers::Error as FileWalkerError;
 
+/// Results are handed off from worker threads to the draining iterator over
+/// a channel this deep, so a burst of quick file reads doesn't force workers
+/// to wait on a slow consumer one item at a time.
+const CHANNEL_CAPACITY: usize = 64;
+
 #[derive(Debug)]
 pub enum Error {
     Processor(ProcessorError),
     FileWalker(FileWalkerError),
+    Io(PathBuf, IoError),
 }
 
 impl std::fmt::Display for Error {
@@ -25,6 +43,7 @@ impl std::fmt::Display for Error {
         match *self {
             Self::Processor(ref err) => write!(f, "processor error: {}", err),
             Self::FileWalker(ref err) => write!(f, "file walker error: {}", err),
+            Self::Io(ref path, ref err) => write!(f, "unable to access {}: {}", path.display(), err),
         }
     }
 }
@@ -34,6 +53,7 @@ impl std::error::Error for Error {
         match *self {
             Self::Processor(ref err) => Some(err),
             Self::FileWalker(ref err) => Some(err),
+            Self::Io(_, ref err) => Some(err),
         }
     }
 }
@@ -117,21 +137,281 @@ impl<'p, 's, 'mrk> FileMetaBlockProducer<'p, 's, 'mrk> {
     }
 }
 
+/// Coordinates `ParallelMetaBlockProducer`'s worker threads over a shared
+/// queue of directories awaiting expansion. `active` counts directories that
+/// have been popped off `pending` but not yet fully expanded; once the first
+/// directory has been pushed and `active` drops to zero with `pending`
+/// empty, there is no more work left for anyone, and `pop` returns `None`
+/// to every worker still waiting on `condvar`. Before the first push, `pop`
+/// waits instead of returning `None`.
+struct DirQueue {
+    state: Mutex<DirQueueState>,
+    condvar: Condvar,
+}
+
+struct DirQueueState {
+    pending: VecDeque<PathBuf>,
+    active: usize,
+    started: bool,
+}
+
+impl DirQueue {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(DirQueueState { pending: VecDeque::new(), active: 0, started: false }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    fn push(&self, dir_path: PathBuf) {
+        let mut state = self.state.lock().unwrap();
+        state.pending.push_back(dir_path);
+        state.started = true;
+        self.condvar.notify_all();
+    }
+
+    fn pop(&self) -> Option<PathBuf> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(dir_path) = state.pending.pop_front() {
+                state.active += 1;
+                return Some(dir_path);
+            }
+            if state.started && state.active == 0 {
+                return None;
+            }
+            state = self.condvar.wait(state).unwrap();
+        }
+    }
+
+    fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.active -= 1;
+        self.condvar.notify_all();
+    }
+}
+
+/// A meta block producer that walks files in parallel across a fixed-size
+/// pool of worker threads, modeled on the `ignore` crate's parallel walker.
+/// Directories awaiting expansion live in a `DirQueue` shared by every
+/// worker; a worker that finishes a directory pushes any subdirectories it
+/// found back onto the queue for whichever worker is free to pick up next,
+/// so the queue behaves like a work-stealing deque without any one worker
+/// owning a fixed slice of the tree. Matched files are parsed in-thread and
+/// their results are sent over a bounded channel that the iterator drains.
+///
+/// Unlike `FileMetaBlockProducer`, every path this producer yields is read
+/// fresh off the filesystem inside a worker thread, so paths are always
+/// owned rather than borrowed from some caller-supplied root; hence
+/// `Cow<'static, Path>` rather than a generic `'p`.
+pub struct ParallelMetaBlockProducer {
+    dir_queue: Arc<DirQueue>,
+    receiver: Receiver<Result<(Cow<'static, Path>, MetaBlock), Error>>,
+    sort_order: SortOrder,
+    sort_after_collection: bool,
+    buffered: Option<std::vec::IntoIter<Result<(Cow<'static, Path>, MetaBlock), Error>>>,
+}
+
+impl ParallelMetaBlockProducer {
+    /// Builds a producer with `thread_count` worker threads, none of which
+    /// do any work until `delve` enqueues a starting directory.
+    pub fn new(
+        meta_format: MetaFormat,
+        selection: Selection,
+        sort_order: SortOrder,
+        thread_count: usize,
+    ) -> Self {
+        let dir_queue = Arc::new(DirQueue::new());
+        let (sender, receiver) = sync_channel(CHANNEL_CAPACITY);
+
+        // Bundled into a `Config` (rather than threaded through as separate
+        // fields) so each worker resolves metadata through the same
+        // `MetaProcessor` entry point the serial producers and `MetaFinalizer`
+        // use, instead of a bespoke call shape of its own.
+        let config = Arc::new(Config {
+            selection,
+            sort_order,
+            item_fn: format!("{}.{}", MetaLocation::Siblings.default_file_name(), meta_format.default_file_extension()),
+            self_fn: format!("{}.{}", MetaLocation::Contains.default_file_name(), meta_format.default_file_extension()),
+            meta_format,
+            ..Config::default()
+        });
+
+        for _ in 0..thread_count.max(1) {
+            let dir_queue = Arc::clone(&dir_queue);
+            let sender = sender.clone();
+            let config = Arc::clone(&config);
+
+            thread::spawn(move || {
+                Self::run_worker(&dir_queue, &sender, &config);
+            });
+        }
+
+        Self {
+            dir_queue,
+            receiver,
+            sort_order,
+            sort_after_collection: false,
+            buffered: None,
+        }
+    }
+
+    /// Enqueues `dir_path` for expansion by whichever worker is free next.
+    /// Called once per root the caller wants walked; subdirectories
+    /// discovered while expanding a root are enqueued automatically.
+    pub fn delve(&mut self, dir_path: &Path) -> Result<(), Error> {
+        self.dir_queue.push(dir_path.to_path_buf());
+        Ok(())
+    }
+
+    /// Forces deterministic output: the iterator buffers every remaining
+    /// item, sorts it by `sort_order`, and replays it in that order. This
+    /// trades throughput (nothing is yielded until every worker is done)
+    /// for the stable ordering that parallel scheduling otherwise loses.
+    pub fn sorted(mut self) -> Self {
+        self.sort_after_collection = true;
+        self
+    }
+
+    fn run_worker(
+        dir_queue: &DirQueue,
+        sender: &SyncSender<Result<(Cow<'static, Path>, MetaBlock), Error>>,
+        config: &Config,
+    ) {
+        while let Some(dir_path) = dir_queue.pop() {
+            let read_dir = match dir_path.read_dir() {
+                Ok(read_dir) => read_dir,
+                Err(err) => {
+                    let _ = sender.send(Err(Error::Io(dir_path, err)));
+                    dir_queue.finish();
+                    continue;
+                },
+            };
+
+            for entry_res in read_dir {
+                let entry = match entry_res {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        let _ = sender.send(Err(Error::Io(dir_path.clone(), err)));
+                        continue;
+                    },
+                };
+
+                let entry_path = entry.path();
+
+                let is_dir = match entry.file_type() {
+                    Ok(file_type) => file_type.is_dir(),
+                    Err(err) => {
+                        let _ = sender.send(Err(Error::Io(entry_path, err)));
+                        continue;
+                    },
+                };
+
+                let selected = match config.selection.is_selected_with(&entry_path, || Ok(is_dir)) {
+                    Ok(selected) => selected,
+                    Err(err) => {
+                        let _ = sender.send(Err(Error::Io(entry_path, err)));
+                        continue;
+                    },
+                };
+
+                if !selected {
+                    continue;
+                }
+
+                if is_dir {
+                    dir_queue.push(entry_path);
+                } else {
+                    let result = MetaProcessor::<YamlMetaReader>::process_item_file(
+                        &entry_path,
+                        MetaLocation::Siblings,
+                        config,
+                    )
+                    .map(|meta_block| (Cow::Owned(entry_path), meta_block))
+                    .map_err(Error::Processor);
+
+                    if sender.send(result).is_err() {
+                        // The draining side is gone; no point finishing the walk.
+                        dir_queue.finish();
+                        return;
+                    }
+                }
+            }
+
+            dir_queue.finish();
+        }
+    }
+
+    /// Orders two results the same way `FileWalker` would have when walking
+    /// serially: by `sort_order` when both produced a block, with any error
+    /// sorted after every success so it doesn't silently disappear or land
+    /// in an unpredictable spot.
+    fn compare(
+        sort_order: SortOrder,
+        a: &Result<(Cow<'static, Path>, MetaBlock), Error>,
+        b: &Result<(Cow<'static, Path>, MetaBlock), Error>,
+    ) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+
+        match (a, b) {
+            (Ok((a_path, _)), Ok((b_path, _))) => match sort_order {
+                SortOrder::Name => a_path.cmp(b_path),
+                SortOrder::ModTime => {
+                    let a_mtime = std::fs::metadata(a_path).and_then(|m| m.modified()).ok();
+                    let b_mtime = std::fs::metadata(b_path).and_then(|m| m.modified()).ok();
+                    a_mtime.cmp(&b_mtime)
+                },
+                _ => Ordering::Equal,
+            },
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => Ordering::Equal,
+        }
+    }
+}
+
+impl Iterator for ParallelMetaBlockProducer {
+    type Item = Result<(Cow<'static, Path>, MetaBlock), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.sort_after_collection {
+            if self.buffered.is_none() {
+                let mut items: Vec<_> = self.receiver.iter().collect();
+                let sort_order = self.sort_order;
+                items.sort_by(|a, b| Self::compare(sort_order, a, b));
+                self.buffered = Some(items.into_iter());
+            }
+
+            return self.buffered.as_mut().unwrap().next();
+        }
+
+        self.receiver.recv().ok()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::MetaBlockProducer;
     use super::FixedMetaBlockProducer;
     use super::FileMetaBlockProducer;
+    use super::ParallelMetaBlockProducer;
 
     use std::borrow::Cow;
     use std::path::Path;
+    use std::path::PathBuf;
     use std::collections::VecDeque;
 
     use bigdecimal::BigDecimal;
 
+    use config::meta_format::MetaFormat;
+    use config::selection::Matcher;
+    use config::selection::Selection;
+    use config::sort_order::SortOrder;
     use metadata::types::MetaKey;
     use metadata::types::MetaVal;
 
+    use test_util::create_temp_media_test_dir;
+
     #[test]
     fn test_fixed_meta_block_producer() {
         let mb_a = btreemap![
@@ -163,4 +443,50 @@ mod tests {
     #[test]
     fn test_file_meta_block_producer() {
     }
+
+    #[test]
+    fn test_parallel_meta_block_producer() {
+        let temp_dir = create_temp_media_test_dir("test_parallel_meta_block_producer");
+        let path = temp_dir.path();
+
+        // Only `.flac` files are items; every directory is selected so the
+        // walk can descend into `ALBUM_01/DISC_01`, exercising `DirQueue`
+        // handing subdirectories discovered by one worker off to whichever
+        // worker pops them next.
+        let selection = Selection::new(
+            Matcher::build(&["*.flac"]).unwrap(),
+            Matcher::empty(),
+            Matcher::any(),
+            Matcher::empty(),
+        );
+
+        let mut producer = ParallelMetaBlockProducer::new(
+            MetaFormat::Yaml,
+            selection,
+            SortOrder::Name,
+            4,
+        ).sorted();
+
+        producer.delve(path).unwrap();
+
+        let results: Vec<_> = producer.collect();
+
+        assert!(results.iter().all(Result::is_ok), "unexpected errors: {:?}", results);
+
+        let mut produced: Vec<PathBuf> = results.into_iter()
+            .map(Result::unwrap)
+            .map(|(item_path, _)| item_path.into_owned())
+            .collect();
+        produced.sort();
+
+        let mut expected = vec![
+            path.join("ALBUM_04.flac"),
+            path.join("ALBUM_01").join("DISC_01").join("TRACK_01.flac"),
+            path.join("ALBUM_01").join("DISC_01").join("TRACK_02.flac"),
+            path.join("ALBUM_01").join("DISC_01").join("TRACK_03.flac"),
+        ];
+        expected.sort();
+
+        assert_eq!(produced, expected);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,232 @@
+//! Walks an entire library tree rooted at a path and produces metadata for
+//! every item in one pass, sitting above `MetaFinalizer` in the pipeline.
+//!
+//! Rather than forcing a caller to invoke `MetaFinalizer::get_metadata` once
+//! per item, `LibraryScanner` enumerates every directory in the tree a
+//! single time and plexes each directory's `item_fn`/`self_fn` once,
+//! feeding the mtime-based `MetaCache` so siblings never cause the same
+//! meta file to be re-read. This follows the job-based location scan model
+//! from Spacedrive, where a long-running scan streams progress and surfaces
+//! non-critical errors to the frontend instead of failing the whole batch.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc::SyncSender;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use library::config::Config;
+use metadata::location::MetaLocation;
+use metadata::processor::MetaCache;
+use metadata::processor::MetaProcessor;
+use metadata::reader::yaml::YamlMetaReader;
+use metadata::types::MetaBlock;
+
+/// An event emitted as a scan progresses, so a caller can render a running
+/// count or a live error log instead of waiting for the whole tree to
+/// finish.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A directory was found during the initial tree walk; the count is the
+    /// running total of directories discovered so far.
+    Discovered(usize),
+    /// An item's metadata was successfully resolved.
+    Processed(PathBuf),
+    /// A meta file could not be processed; the scan continues past it.
+    Failed(PathBuf, String),
+}
+
+fn report(progress: &Option<SyncSender<Event>>, event: Event) {
+    if let Some(sender) = progress {
+        // Best-effort: a consumer that isn't keeping up just misses some
+        // events rather than stalling the scan.
+        let _ = sender.try_send(event);
+    }
+}
+
+/// The non-fatal report of a full library scan, returned alongside the
+/// resolved metadata: how many items resolved, and which meta files could
+/// not be processed and why.
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    pub succeeded: usize,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+pub struct LibraryScanner;
+
+impl LibraryScanner {
+    /// Walks `root` sequentially, returning metadata for every item found
+    /// under it, plus a report of any per-item failures.
+    pub fn scan<P: AsRef<Path>>(
+        root: P,
+        config: &Config,
+        progress: Option<SyncSender<Event>>,
+    ) -> (HashMap<PathBuf, MetaBlock>, ScanReport) {
+        let dirs = Self::discover_dirs(root.as_ref(), config, &progress);
+
+        let mut cache = MetaCache::new();
+        let mut meta_blocks = HashMap::new();
+        let mut report_out = ScanReport::default();
+
+        for dir_path in &dirs {
+            Self::process_dir(dir_path, config, &mut cache, &mut meta_blocks, &mut report_out, &progress);
+        }
+
+        (meta_blocks, report_out)
+    }
+
+    /// Parallel counterpart to `scan`, distributing per-directory plexing
+    /// across a rayon thread pool. Directories don't share any meta paths
+    /// with one another (each is plexed from its own `item_fn`/`self_fn`),
+    /// so each task keeps its own local `MetaCache` rather than contending
+    /// on one shared behind a lock — sharing it would serialize the
+    /// expensive read/parse/plex work that this method exists to
+    /// parallelize in the first place.
+    #[cfg(feature = "rayon")]
+    pub fn par_scan<P: AsRef<Path>>(
+        root: P,
+        config: &Config,
+        progress: Option<SyncSender<Event>>,
+    ) -> (HashMap<PathBuf, MetaBlock>, ScanReport) {
+        let dirs = Self::discover_dirs(root.as_ref(), config, &progress);
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.max_workers.max(1))
+            .build()
+            .unwrap();
+
+        let partials: Vec<(HashMap<PathBuf, MetaBlock>, ScanReport)> = pool.install(|| {
+            dirs.into_par_iter()
+                .map(|dir_path| {
+                    let mut cache = MetaCache::new();
+                    let mut meta_blocks = HashMap::new();
+                    let mut report_out = ScanReport::default();
+
+                    Self::process_dir(&dir_path, config, &mut cache, &mut meta_blocks, &mut report_out, &progress);
+
+                    (meta_blocks, report_out)
+                })
+                .collect()
+        });
+
+        let mut meta_blocks = HashMap::new();
+        let mut report_out = ScanReport::default();
+
+        // `partials` preserves `dirs`'s original (parent-before-child) BFS
+        // order, since rayon's indexed `collect` doesn't reorder by
+        // completion time, so merging it back in this order gives the same
+        // Siblings-then-Contains composite per item that `scan` produces.
+        for (blocks, sub_report) in partials {
+            for (path, meta_block) in blocks {
+                Self::merge_insert(&mut meta_blocks, path, meta_block);
+            }
+            report_out.succeeded += sub_report.succeeded;
+            report_out.failed.extend(sub_report.failed);
+        }
+
+        (meta_blocks, report_out)
+    }
+
+    /// Enumerates every directory in the tree rooted at `root`, pruning
+    /// subtrees that fail the directory selection as soon as they're found,
+    /// so excluded trees are never `read_dir`'d.
+    fn discover_dirs(root: &Path, config: &Config, progress: &Option<SyncSender<Event>>) -> Vec<PathBuf> {
+        let mut dirs = vec![root.to_owned()];
+        let mut pending = VecDeque::new();
+        pending.push_back(root.to_owned());
+
+        report(progress, Event::Discovered(dirs.len()));
+
+        while let Some(dir_path) = pending.pop_front() {
+            let entries = match dir_path.read_dir() {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for entry in entries.filter_map(Result::ok) {
+                let entry_path = entry.path();
+                let is_selected_dir = entry.file_type()
+                    .map(|ft| ft.is_dir())
+                    .unwrap_or(false)
+                    && config.selection.is_selected_with(&entry_path, || Ok(true)).unwrap_or(false);
+
+                if is_selected_dir {
+                    dirs.push(entry_path.clone());
+                    pending.push_back(entry_path);
+
+                    report(progress, Event::Discovered(dirs.len()));
+                }
+            }
+        }
+
+        dirs
+    }
+
+    /// Plexes a single directory's `item_fn` (describing its selected
+    /// children) and `self_fn` (describing the directory itself), feeding
+    /// both through `cache`, and records the outcome in `meta_blocks`/
+    /// `report_out`.
+    fn process_dir(
+        dir_path: &Path,
+        config: &Config,
+        cache: &mut MetaCache,
+        meta_blocks: &mut HashMap<PathBuf, MetaBlock>,
+        report_out: &mut ScanReport,
+        progress: &Option<SyncSender<Event>>,
+    ) {
+        let item_path = dir_path.join(&config.item_fn);
+
+        Self::process_meta_file(&item_path, MetaLocation::Siblings, config, cache, meta_blocks, report_out, progress);
+
+        let self_path = dir_path.join(&config.self_fn);
+
+        Self::process_meta_file(&self_path, MetaLocation::Contains, config, cache, meta_blocks, report_out, progress);
+    }
+
+    fn process_meta_file(
+        meta_path: &Path,
+        meta_location: MetaLocation,
+        config: &Config,
+        cache: &mut MetaCache,
+        meta_blocks: &mut HashMap<PathBuf, MetaBlock>,
+        report_out: &mut ScanReport,
+        progress: &Option<SyncSender<Event>>,
+    ) {
+        if !meta_path.is_file() {
+            return;
+        }
+
+        match MetaProcessor::<YamlMetaReader>::process_meta_file_cached(meta_path, meta_location, config, cache, false) {
+            Ok(plexed) => {
+                for (path, meta_block) in plexed.clone() {
+                    report(progress, Event::Processed(path.clone()));
+                    Self::merge_insert(meta_blocks, path, meta_block);
+                    report_out.succeeded += 1;
+                }
+            },
+            Err(err) => {
+                report(progress, Event::Failed(meta_path.to_owned(), err.to_string()));
+                report_out.failed.push((meta_path.to_owned(), err.to_string()));
+            },
+        }
+    }
+
+    /// Inserts `meta_block` under `path`, composited onto any block already
+    /// present rather than clobbering it. A directory's `Siblings` entry
+    /// (assigned while scanning its parent's `item_fn`) and `Contains` entry
+    /// (assigned while scanning its own `self_fn`) land under the same path
+    /// from two separate plexes, processed in that order (parent before
+    /// self, per the BFS discovery order both `scan` and `par_scan` process
+    /// `dirs` in), so composing new-over-existing here gives the same
+    /// Siblings-then-Contains result `MetaFinalizer` produces.
+    fn merge_insert(meta_blocks: &mut HashMap<PathBuf, MetaBlock>, path: PathBuf, meta_block: MetaBlock) {
+        match meta_blocks.get_mut(&path) {
+            Some(existing) => existing.extend(meta_block),
+            None => { meta_blocks.insert(path, meta_block); },
+        }
+    }
+}
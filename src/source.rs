@@ -1,14 +1,44 @@
 use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::ReadDir;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::SyncSender;
 
 use thiserror::Error;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 use crate::config::selection::Selection;
 use crate::metadata::schema::SchemaFormat;
 use crate::util::NameError;
 use crate::util::Util;
 
+/// How many entries are processed between each `ProgressData` update emitted
+/// by `ItemPaths`, `SelectedItemPaths`, and `MetaWalk`.
+const PROGRESS_INTERVAL: usize = 32;
+
+/// A periodic snapshot of a scan's progress, sent every `PROGRESS_INTERVAL`
+/// entries by `ItemPaths`, `SelectedItemPaths`, or `MetaWalk` once a sender
+/// has been registered via their `with_progress` builder method. The
+/// channel is drained independently of iteration: updates are pushed with
+/// `try_send`, so a consumer that isn't keeping up just misses some of them
+/// rather than stalling the scan. Not registering a sender (the default)
+/// skips this bookkeeping entirely.
+#[derive(Debug, Clone)]
+pub struct ProgressData {
+    pub entries_checked: usize,
+    pub current_dir: PathBuf,
+    pub selected_so_far: usize,
+}
+
+fn report_progress(progress: &Option<SyncSender<ProgressData>>, data: ProgressData) {
+    if let Some(sender) = progress {
+        let _ = sender.try_send(data);
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("not a directory: {}", .0.display())]
@@ -30,8 +60,24 @@ pub enum Error {
     IterDir(#[source] IoError),
     // #[error("unable to read item directory entry: {0}")]
     // IterDirEntry(#[source] IoError),
+
+    #[error(r#"cannot resolve symlink "{}": {1}"#, .0.display())]
+    SymlinkAccess(PathBuf, #[source] IoError),
+    #[error("too many symlink hops resolving: {}", .0.display())]
+    TooManySymlinkHops(PathBuf),
+    #[error(
+        "symlink cycle detected: \"{}\" was already visited as an ancestor of \"{}\"",
+        .ancestor.display(),
+        .child.display(),
+    )]
+    LoopDetected { ancestor: PathBuf, child: PathBuf },
 }
 
+/// The maximum number of symlink hops `MetaWalk` will resolve for any single
+/// path before giving up, so that a cycle of mutually-referential symlinks
+/// cannot hang the walker even before the `(device, inode)` check applies.
+const MAX_SYMLINK_HOPS: usize = 40;
+
 impl Error {
     pub(crate) fn is_fatal(&self) -> bool {
         match self {
@@ -173,7 +219,14 @@ impl Source {
                 }
             };
 
-            Ok(ItemPaths(ipi))
+            Ok(ItemPaths {
+                inner: ipi,
+                current_dir: meta_parent_dir_path.to_path_buf(),
+                entries_checked: 0,
+                progress: None,
+                order_by: None,
+                buffered: None,
+            })
         } else {
             // This should never happen, since at this point we have a real meta
             // file and thus, a real parent directory for that file, but making
@@ -189,7 +242,13 @@ impl Source {
         meta_path: &'a Path,
         selection: &'a Selection,
     ) -> Result<SelectedItemPaths<'a>, Error> {
-        Ok(SelectedItemPaths(self.item_paths(meta_path)?, selection))
+        Ok(SelectedItemPaths {
+            item_paths: self.item_paths(meta_path)?,
+            selection,
+            entries_checked: 0,
+            selected_so_far: 0,
+            progress: None,
+        })
     }
 }
 
@@ -213,6 +272,87 @@ impl Sourcer {
             item_path,
         }
     }
+
+    /// Parallel counterpart to `meta_paths`, distributing the per-item stat
+    /// work across a thread pool. For each item path, this yields the same
+    /// result `meta_paths(item_path).next()` would: the first source whose
+    /// meta path resolves without a non-fatal error, or its fatal error.
+    /// Items for which no source matched (and nothing fatal occurred) are
+    /// omitted, same as `MetaPaths` exhausting with no match.
+    ///
+    /// Results are collected back in the same order as `item_paths`,
+    /// regardless of which thread finished first.
+    #[cfg(feature = "rayon")]
+    pub fn par_meta_paths<'a>(
+        &'a self,
+        item_paths: impl IntoParallelIterator<Item = PathBuf>,
+    ) -> Vec<Result<(PathBuf, &'a Source), Error>> {
+        let mut indexed: Vec<(usize, Result<(PathBuf, &'a Source), Error>)> = item_paths
+            .into_par_iter()
+            .enumerate()
+            .filter_map(|(idx, item_path)| {
+                Self::first_meta_path(&self.0, &item_path).map(|res| (idx, res))
+            })
+            .collect();
+
+        indexed.sort_by_key(|(idx, _)| *idx);
+        indexed.into_iter().map(|(_, res)| res).collect()
+    }
+
+    /// The logic `MetaPaths::next` applies for a single item path: the first
+    /// source whose meta path resolves without a non-fatal error, or its
+    /// fatal error; `None` if every source failed non-fatally.
+    #[cfg(feature = "rayon")]
+    fn first_meta_path<'a>(
+        sources: &'a [Source],
+        item_path: &Path,
+    ) -> Option<Result<(PathBuf, &'a Source), Error>> {
+        for source in sources {
+            match source.meta_path(item_path) {
+                Ok(meta_path) => return Some(Ok((meta_path, source))),
+                Err(err) if err.is_fatal() => return Some(Err(err)),
+                Err(_) => continue,
+            }
+        }
+
+        None
+    }
+
+    /// Recursively descends the item tree rooted at `root`, applying every
+    /// configured `Source` at each item (file or directory) it visits and
+    /// yielding a `(item_path, meta_path, &Source)` tuple for each match.
+    ///
+    /// Unlike `meta_paths`, which only ever looks at a single item, this
+    /// walks the whole subtree, so a directory being excluded from descent
+    /// by `max_depth` does not stop its own matches (if any) from being
+    /// yielded; it just stops its children from being visited.
+    pub fn walk<'a>(&'a self, root: &Path) -> IoResult<MetaWalk<'a>> {
+        let read_dir = root.read_dir()?;
+        let root_meta = std::fs::metadata(root)?;
+        let dev_ino = (root_meta.dev(), root_meta.ino());
+
+        Ok(MetaWalk {
+            sourcer: self,
+            stack: vec![WalkFrame {
+                dir_path: root.to_path_buf(),
+                depth: 0,
+                read_dir,
+                deferred: Vec::new(),
+                dev_ino,
+            }],
+            max_depth: None,
+            min_depth: 0,
+            contents_first: false,
+            follow_links: false,
+            pending: VecDeque::new(),
+            started: false,
+            done: false,
+            entries_checked: 0,
+            selected_so_far: 0,
+            progress: None,
+            selection: None,
+        })
+    }
 }
 
 enum ItemPathsInner<'a> {
@@ -231,32 +371,157 @@ impl<'a> Iterator for ItemPathsInner<'a> {
     }
 }
 
-pub struct ItemPaths<'a>(ItemPathsInner<'a>);
+/// Controls the order `ItemPaths`/`SelectedItemPaths` yield entries in. Left
+/// unset (the default), entries stream directly out of `ReadDir` as they're
+/// read, in whatever order the filesystem happens to produce — fast, but
+/// not reproducible across platforms or even repeat runs. Setting an
+/// `OrderBy` buffers every entry up front, sorts it once, and yields from
+/// that stable order instead, mirroring the `sort_by`/`sort_by_file_name`
+/// knobs other directory walkers expose.
+pub enum OrderBy {
+    FileName,
+    FileNameCaseInsensitive,
+    Custom(Box<dyn Fn(&Path, &Path) -> std::cmp::Ordering>),
+}
+
+impl OrderBy {
+    fn compare(&self, a: &Path, b: &Path) -> std::cmp::Ordering {
+        match self {
+            Self::FileName => a.file_name().cmp(&b.file_name()),
+            Self::FileNameCaseInsensitive => {
+                let lower = |p: &Path| p.file_name().map(|n| n.to_string_lossy().to_lowercase());
+                lower(a).cmp(&lower(b))
+            }
+            Self::Custom(cmp) => cmp(a, b),
+        }
+    }
+}
+
+pub struct ItemPaths<'a> {
+    inner: ItemPathsInner<'a>,
+    current_dir: PathBuf,
+    entries_checked: usize,
+    progress: Option<SyncSender<ProgressData>>,
+    order_by: Option<OrderBy>,
+    buffered: Option<std::vec::IntoIter<IoResult<Cow<'a, Path>>>>,
+}
+
+impl<'a> ItemPaths<'a> {
+    /// Registers a channel to receive a `ProgressData` update every
+    /// `PROGRESS_INTERVAL` entries checked. Without this, scanning incurs no
+    /// overhead beyond a per-entry counter increment.
+    pub fn with_progress(mut self, progress: SyncSender<ProgressData>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Buffers and sorts all entries according to `order_by` before
+    /// yielding any of them, instead of streaming them unordered.
+    pub fn order_by(mut self, order_by: OrderBy) -> Self {
+        self.order_by = Some(order_by);
+        self
+    }
+
+    fn buffer_and_sort(&mut self, order_by: OrderBy) {
+        let mut entries: Vec<IoResult<Cow<'a, Path>>> = std::iter::from_fn(|| self.inner.next()).collect();
+
+        entries.sort_by(|a, b| match (a, b) {
+            (Ok(pa), Ok(pb)) => order_by.compare(pa, pb),
+            (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+            (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+            (Err(_), Err(_)) => std::cmp::Ordering::Equal,
+        });
+
+        self.buffered = Some(entries.into_iter());
+    }
+}
 
 impl<'a> Iterator for ItemPaths<'a> {
     type Item = IoResult<Cow<'a, Path>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+        if let Some(order_by) = self.order_by.take() {
+            self.buffer_and_sort(order_by);
+        }
+
+        let item = match &mut self.buffered {
+            Some(buffered) => buffered.next(),
+            None => self.inner.next(),
+        };
+
+        if item.is_some() {
+            self.entries_checked += 1;
+
+            if self.progress.is_some() && self.entries_checked % PROGRESS_INTERVAL == 0 {
+                report_progress(&self.progress, ProgressData {
+                    entries_checked: self.entries_checked,
+                    current_dir: self.current_dir.clone(),
+                    // `ItemPaths` performs no selection of its own, so it has
+                    // no meaningful selected count to report; only
+                    // `SelectedItemPaths` and `MetaWalk` actually select.
+                    selected_so_far: 0,
+                });
+            }
+        }
+
+        item
     }
 }
 
-pub struct SelectedItemPaths<'a>(ItemPaths<'a>, &'a Selection);
+pub struct SelectedItemPaths<'a> {
+    item_paths: ItemPaths<'a>,
+    selection: &'a Selection,
+    entries_checked: usize,
+    selected_so_far: usize,
+    progress: Option<SyncSender<ProgressData>>,
+}
+
+impl<'a> SelectedItemPaths<'a> {
+    /// Registers a channel to receive a `ProgressData` update every
+    /// `PROGRESS_INTERVAL` entries checked, with `selected_so_far` counting
+    /// how many have passed selection so far.
+    pub fn with_progress(mut self, progress: SyncSender<ProgressData>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Buffers and sorts the underlying item paths according to `order_by`
+    /// before selection filtering runs, instead of streaming them unordered.
+    pub fn order_by(mut self, order_by: OrderBy) -> Self {
+        self.item_paths = self.item_paths.order_by(order_by);
+        self
+    }
+
+    fn maybe_report(&self) {
+        if self.progress.is_some() && self.entries_checked % PROGRESS_INTERVAL == 0 {
+            report_progress(&self.progress, ProgressData {
+                entries_checked: self.entries_checked,
+                current_dir: self.item_paths.current_dir.clone(),
+                selected_so_far: self.selected_so_far,
+            });
+        }
+    }
+}
 
 impl<'a> Iterator for SelectedItemPaths<'a> {
     type Item = IoResult<Cow<'a, Path>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some(res) = self.0.next() {
+        while let Some(res) = self.item_paths.next() {
+            self.entries_checked += 1;
+
             match res {
                 Err(err) => {
                     return Some(Err(err));
                 }
-                Ok(path) => match self.1.is_selected(&path) {
+                Ok(path) => match self.selection.is_selected(&path) {
                     Ok(true) => {
+                        self.selected_so_far += 1;
+                        self.maybe_report();
                         return Some(Ok(path));
                     }
                     Ok(false) => {
+                        self.maybe_report();
                         continue;
                     }
                     Err(err) => {
@@ -270,6 +535,318 @@ impl<'a> Iterator for SelectedItemPaths<'a> {
     }
 }
 
+struct WalkFrame<'a> {
+    dir_path: PathBuf,
+    depth: usize,
+    read_dir: ReadDir,
+    /// This directory's own matches, held back so they can be emitted after
+    /// its children when `contents_first` is set.
+    deferred: Vec<(PathBuf, PathBuf, &'a Source)>,
+    /// The `(device, inode)` pair this directory was entered with, used to
+    /// detect symlink cycles when `follow_links` is set.
+    dev_ino: (u64, u64),
+}
+
+/// A stack-based depth-first walk of an item tree, applying a `Sourcer`'s
+/// configured `Source`s at every item it visits. See `Sourcer::walk`.
+pub struct MetaWalk<'a> {
+    sourcer: &'a Sourcer,
+    stack: Vec<WalkFrame<'a>>,
+    max_depth: Option<usize>,
+    min_depth: usize,
+    contents_first: bool,
+    follow_links: bool,
+    pending: VecDeque<Result<(PathBuf, PathBuf, &'a Source), Error>>,
+    started: bool,
+    done: bool,
+    entries_checked: usize,
+    selected_so_far: usize,
+    progress: Option<SyncSender<ProgressData>>,
+    selection: Option<&'a Selection>,
+}
+
+impl<'a> MetaWalk<'a> {
+    /// Caps how deep the walk descends below the root (which is depth `0`).
+    /// A directory at exactly `max_depth` is still visited (and its own
+    /// matches yielded), but its children are not.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Suppresses matches found above `min_depth`, without affecting which
+    /// directories are descended into.
+    pub fn min_depth(mut self, min_depth: usize) -> Self {
+        self.min_depth = min_depth;
+        self
+    }
+
+    /// When set, a directory's own matches are yielded after its children's,
+    /// instead of before (the default).
+    pub fn contents_first(mut self, contents_first: bool) -> Self {
+        self.contents_first = contents_first;
+        self
+    }
+
+    /// When set, a symlink to a directory is resolved and descended into as
+    /// if it were an ordinary directory, guarded by a symlink-hop cap and
+    /// `(device, inode)`-based cycle detection (see `Error::TooManySymlinkHops`
+    /// and `Error::LoopDetected`). The default does not follow symlinks at
+    /// all, matching `item_paths`' existing behavior.
+    pub fn follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Registers a channel to receive a `ProgressData` update every
+    /// `PROGRESS_INTERVAL` entries visited, with `current_dir` tracking
+    /// whichever directory is currently being read and `selected_so_far`
+    /// counting total matches queued so far.
+    pub fn with_progress(mut self, progress: SyncSender<ProgressData>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Restricts the walk to entries that pass `selection`, pruning
+    /// excluded subtrees before they are ever read — the same pruning
+    /// `Selection::select_in_dir_recursive` applies. Build `selection` with
+    /// `Selection::from_rules` or `Selection::with_ignore_files` to honor
+    /// gitignore-style, per-directory rule files for the duration of this
+    /// walk.
+    pub fn with_selection(mut self, selection: &'a Selection) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    fn maybe_report(&self, current_dir: &Path) {
+        if self.progress.is_some() && self.entries_checked % PROGRESS_INTERVAL == 0 {
+            report_progress(&self.progress, ProgressData {
+                entries_checked: self.entries_checked,
+                current_dir: current_dir.to_path_buf(),
+                selected_so_far: self.selected_so_far,
+            });
+        }
+    }
+
+    fn is_within_depth(&self, depth: usize) -> bool {
+        depth >= self.min_depth && self.max_depth.map_or(true, |max| depth <= max)
+    }
+
+    fn matches_for(&self, item_path: &Path) -> Result<Vec<(PathBuf, PathBuf, &'a Source)>, Error> {
+        // Same "first non-failing source wins, fatal errors propagate" logic
+        // as `MetaPaths::next`, but collecting every source's match rather
+        // than stopping at the first, and borrowing `Source`s for `'a`
+        // (tied to the walk itself) rather than the per-entry `item_path`.
+        let mut matches = Vec::new();
+
+        for source in &self.sourcer.0 {
+            match source.meta_path(item_path) {
+                Ok(meta_path) => matches.push((item_path.to_path_buf(), meta_path, source)),
+                Err(err) if err.is_fatal() => return Err(err),
+                Err(_) => continue,
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Resolves the chain of symlinks starting at `path`, following at most
+    /// `MAX_SYMLINK_HOPS` links. Returns the final, non-link path, or an
+    /// error if the chain is too long or a link can't be read.
+    fn resolve_symlink_capped(path: &Path) -> Result<PathBuf, Error> {
+        let mut current = path.to_path_buf();
+
+        // One more check than hops, so the target of the last allowed hop is
+        // still inspected.
+        for _ in 0..=MAX_SYMLINK_HOPS {
+            let metadata = std::fs::symlink_metadata(&current)
+                .map_err(|io_err| Error::SymlinkAccess(path.to_path_buf(), io_err))?;
+
+            if !metadata.file_type().is_symlink() {
+                return Ok(current);
+            }
+
+            let target = std::fs::read_link(&current)
+                .map_err(|io_err| Error::SymlinkAccess(path.to_path_buf(), io_err))?;
+
+            current = if target.is_absolute() {
+                target
+            } else {
+                current.parent().map(|p| p.join(&target)).unwrap_or(target)
+            };
+        }
+
+        Err(Error::TooManySymlinkHops(path.to_path_buf()))
+    }
+}
+
+impl<'a> Iterator for MetaWalk<'a> {
+    type Item = Result<(PathBuf, PathBuf, &'a Source), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if !self.started {
+            self.started = true;
+
+            let root_depth = self.stack[0].depth;
+            let root_path = self.stack[0].dir_path.clone();
+
+            if self.is_within_depth(root_depth) {
+                match self.matches_for(&root_path) {
+                    Err(err) => {
+                        self.done = true;
+                        return Some(Err(err));
+                    },
+                    Ok(matches) => {
+                        if self.contents_first {
+                            self.stack[0].deferred = matches;
+                        } else {
+                            self.pending.extend(matches.into_iter().map(Ok));
+                        }
+                    },
+                }
+            }
+        }
+
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+
+            let frame = match self.stack.last_mut() {
+                Some(frame) => frame,
+                None => {
+                    self.done = true;
+                    return None;
+                },
+            };
+
+            match frame.read_dir.next() {
+                None => {
+                    // This directory is exhausted; if its own matches were
+                    // deferred (contents-first), emit them now, then pop.
+                    let mut frame = self.stack.pop().unwrap();
+                    if !frame.deferred.is_empty() {
+                        self.pending.extend(frame.deferred.drain(..).map(Ok));
+                    }
+                },
+                Some(Err(io_err)) => return Some(Err(Error::IterDir(io_err))),
+                Some(Ok(dir_entry)) => {
+                    let entry_path = dir_entry.path();
+                    let depth = frame.depth + 1;
+                    let dir_path = frame.dir_path.clone();
+
+                    self.entries_checked += 1;
+
+                    let file_type = match dir_entry.file_type() {
+                        Ok(ft) => ft,
+                        Err(io_err) => return Some(Err(Error::IterDir(io_err))),
+                    };
+
+                    // Resolve a symlink to its target when following links;
+                    // otherwise a symlinked directory is treated as a leaf,
+                    // same as the non-following default.
+                    let resolve_path = if self.follow_links && file_type.is_symlink() {
+                        match Self::resolve_symlink_capped(&entry_path) {
+                            Ok(resolved) => Some(resolved),
+                            Err(err) => {
+                                self.done = true;
+                                return Some(Err(err));
+                            },
+                        }
+                    } else {
+                        None
+                    };
+
+                    let is_dir = match &resolve_path {
+                        Some(resolved) => match std::fs::metadata(resolved) {
+                            Ok(metadata) => metadata.is_dir(),
+                            Err(io_err) => return Some(Err(Error::SymlinkAccess(entry_path.clone(), io_err))),
+                        },
+                        None => file_type.is_dir(),
+                    };
+
+                    // An excluded directory is pruned before it's ever read,
+                    // same as `Selection::select_in_dir_recursive`; an
+                    // excluded file simply contributes no matches.
+                    let is_selected = match self.selection {
+                        Some(selection) => match selection.is_selected_with(&entry_path, || Ok(is_dir)) {
+                            Ok(selected) => selected,
+                            Err(io_err) => return Some(Err(Error::ItemAccess(entry_path.clone(), io_err))),
+                        },
+                        None => true,
+                    };
+
+                    let matches = if is_selected && self.is_within_depth(depth) {
+                        match self.matches_for(&entry_path) {
+                            Err(err) => {
+                                self.done = true;
+                                return Some(Err(err));
+                            },
+                            Ok(matches) => matches,
+                        }
+                    } else {
+                        Vec::new()
+                    };
+
+                    if is_dir && is_selected && self.max_depth.map_or(true, |max| depth < max) {
+                        let descend_path = resolve_path.unwrap_or_else(|| entry_path.clone());
+
+                        let dev_ino = match std::fs::metadata(&descend_path) {
+                            Ok(metadata) => (metadata.dev(), metadata.ino()),
+                            Err(io_err) => return Some(Err(Error::SymlinkAccess(entry_path.clone(), io_err))),
+                        };
+
+                        if self.follow_links {
+                            if let Some(ancestor) = self.stack.iter().find(|f| f.dev_ino == dev_ino) {
+                                self.done = true;
+                                return Some(Err(Error::LoopDetected {
+                                    ancestor: ancestor.dir_path.clone(),
+                                    child: entry_path,
+                                }));
+                            }
+                        }
+
+                        let read_dir = match descend_path.read_dir() {
+                            Ok(read_dir) => read_dir,
+                            Err(io_err) => return Some(Err(Error::IterDir(io_err))),
+                        };
+
+                        self.selected_so_far += matches.len();
+
+                        if self.contents_first {
+                            self.stack.push(WalkFrame {
+                                dir_path: entry_path,
+                                depth,
+                                read_dir,
+                                deferred: matches,
+                                dev_ino,
+                            });
+                        } else {
+                            self.stack.push(WalkFrame {
+                                dir_path: entry_path,
+                                depth,
+                                read_dir,
+                                deferred: Vec::new(),
+                                dev_ino,
+                            });
+                            self.pending.extend(matches.into_iter().map(Ok));
+                        }
+                    } else {
+                        self.selected_so_far += matches.len();
+                        self.pending.extend(matches.into_iter().map(Ok));
+                    }
+
+                    self.maybe_report(&dir_path);
+                },
+            }
+        }
+    }
+}
+
 pub struct MetaPaths<'a> {
     iter: std::slice::Iter<'a, Source>,
     item_path: &'a Path,